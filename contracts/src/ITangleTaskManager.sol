@@ -19,6 +19,11 @@ interface ITangleTaskManager {
         uint256 timestamp
     );
 
+    event TaskResponded(
+        TaskResponse taskResponse,
+        TaskResponseMetadata taskResponseMetadata
+    );
+
     // STRUCTS
 
     struct GaiaNodeConfig {
@@ -39,10 +44,18 @@ interface ITangleTaskManager {
     // Task response is hashed and signed by operators.
     // these signatures are aggregated and sent to the contract as response.
     struct TaskResponse {
-        // Can be obtained by the operator from the event NewTaskCreated.
+        // The Gaia node task the attestation is about, from the GaiaNodeStarted event.
         uint32 referenceTaskIndex;
-        // This is just the response that the operator has to compute by itself.
-        uint256 numberSquared;
+        // Block the operator state used to verify the signatures is taken from.
+        uint32 referenceBlockNumber;
+        // Liveness of the node as probed by the signing operators.
+        bool isRunning;
+        // Seconds since the task was started, as of the reference block.
+        uint256 uptime;
+        // Model served by the node's OpenAI-compatible API.
+        string model;
+        // Public URL of the node's OpenAI-compatible API, probed by challengers.
+        string endpoint;
     }
 
     // Extra information related to taskResponse, which is filled inside the contract.
@@ -64,4 +77,10 @@ interface ITangleTaskManager {
     function stopGaiaNode(uint32 taskId) external;
 
     function getGaiaNodeStatus(uint32 taskId) external view returns (GaiaNodeStatus memory);
+
+    // NOTE: returns the configuration a Gaia node was started with.
+    function getGaiaNodeConfig(uint32 taskId) external view returns (GaiaNodeConfig memory);
+
+    // NOTE: returns the ids of the tasks of an operator that are still running.
+    function getOperatorTasks(address operator) external view returns (uint32[] memory);
 }
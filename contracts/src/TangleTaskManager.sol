@@ -27,11 +27,17 @@ contract TangleTaskManager is
     uint32 public immutable TASK_RESPONSE_WINDOW_BLOCK;
     uint32 public constant TASK_CHALLENGE_WINDOW_BLOCK = 100;
     uint256 internal constant _THRESHOLD_DENOMINATOR = 100;
+    // Quorums whose operators attest node liveness, and the share of their stake that has to sign
+    bytes public constant QUORUM_NUMBERS = hex"00";
+    uint32 public constant QUORUM_THRESHOLD_PERCENTAGE = 67;
 
     /* STORAGE */
+    // The contract sits behind a proxy, new variables go after `generator` to keep the slots
+    // of the existing ones
     // The latest task index
     uint32 public latestTaskNum;
     mapping(uint32 => GaiaNodeConfig) private nodeConfigs;
+    // Running tasks of each operator, a task is removed once it is stopped
     mapping(address => uint32[]) private operatorTasks;
 
     // Task hashes
@@ -106,6 +112,7 @@ contract TangleTaskManager is
             startTime: block.timestamp,
             operator: msg.sender
         });
+        operatorTasks[msg.sender].push(taskId);
 
         emit GaiaNodeStarted(
             taskId,
@@ -114,6 +121,8 @@ contract TangleTaskManager is
             msg.sender,
             block.timestamp
         );
+
+        return taskId;
     }
 
     function stopGaiaNode(
@@ -124,6 +133,7 @@ contract TangleTaskManager is
         require(nodeConfigs[taskId].isRunning, "Node is not running");
         
         config.isRunning = false;
+        _removeOperatorTask(msg.sender, taskId);
 
         emit GaiaNodeStopped(
             taskId,
@@ -132,6 +142,50 @@ contract TangleTaskManager is
         );
     }
 
+    // NOTE: this function records an aggregated liveness attestation signed by the operators
+    function respondToTask(
+        TaskResponse calldata taskResponse,
+        NonSignerStakesAndSignature memory nonSignerStakesAndSignature
+    ) external onlyAggregator {
+        uint32 taskId = taskResponse.referenceTaskIndex;
+        require(nodeConfigs[taskId].operator != address(0), "Task ID does not exist");
+        require(
+            taskResponse.referenceBlockNumber < block.number,
+            "Reference block must be in the past"
+        );
+        require(
+            uint32(block.number) <= taskResponse.referenceBlockNumber + TASK_RESPONSE_WINDOW_BLOCK,
+            "Aggregator has responded to the task too late"
+        );
+
+        bytes32 message = keccak256(abi.encode(taskResponse));
+        (
+            QuorumStakeTotals memory quorumStakeTotals,
+            bytes32 hashOfNonSigners
+        ) = checkSignatures(
+                message,
+                QUORUM_NUMBERS,
+                taskResponse.referenceBlockNumber,
+                nonSignerStakesAndSignature
+            );
+
+        for (uint256 i = 0; i < QUORUM_NUMBERS.length; i++) {
+            require(
+                quorumStakeTotals.signedStakeForQuorum[i] * _THRESHOLD_DENOMINATOR >=
+                    quorumStakeTotals.totalStakeForQuorum[i] * QUORUM_THRESHOLD_PERCENTAGE,
+                "Signatories do not own at least threshold percentage of a quorum"
+            );
+        }
+
+        TaskResponseMetadata memory taskResponseMetadata = TaskResponseMetadata(
+            uint32(block.number),
+            hashOfNonSigners
+        );
+        allTaskResponses[taskId] = keccak256(abi.encode(taskResponse, taskResponseMetadata));
+
+        emit TaskResponded(taskResponse, taskResponseMetadata);
+    }
+
     function getGaiaNodeStatus(uint32 taskId)
       external view override returns (GaiaNodeStatus memory)
     {
@@ -139,6 +193,35 @@ contract TangleTaskManager is
       require(config.operator != address(0), "Task ID does not exist");
       GaiaNodeStatus memory status;
       status.isRunning = config.isRunning;
+      status.operator = config.operator;
+      if (config.isRunning) {
+        status.uptime = block.timestamp - config.startTime;
+      }
       return status;
     }
+
+    function getGaiaNodeConfig(uint32 taskId)
+      external view override returns (GaiaNodeConfig memory)
+    {
+      GaiaNodeConfig storage config = nodeConfigs[taskId];
+      require(config.operator != address(0), "Task ID does not exist");
+      return config;
+    }
+
+    function getOperatorTasks(address operator)
+      external view override returns (uint32[] memory)
+    {
+      return operatorTasks[operator];
+    }
+
+    function _removeOperatorTask(address operator, uint32 taskId) internal {
+      uint32[] storage tasks = operatorTasks[operator];
+      for (uint256 i = 0; i < tasks.length; i++) {
+        if (tasks[i] == taskId) {
+          tasks[i] = tasks[tasks.length - 1];
+          tasks.pop();
+          return;
+        }
+      }
+    }
 }
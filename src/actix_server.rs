@@ -1,35 +1,211 @@
-use crate::gaia_manager::GaiaNodeManager;
-use crate::types::GaiaNodeConfig;
+use crate::journal::EventJournal;
+use crate::metrics::{self, METRICS};
+use crate::node_logs::LogLine;
+use crate::node_registry::{NodeRegistry, LOCAL_TASK_ID};
+use crate::types::{GaiaError, GaiaNodeConfig, GaiaNodeStatus, ResourceUsage};
+use actix_web::dev::Service;
 use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::broadcast::error::RecvError;
 
-/// Shared application state - contains the Gaia node manager
+/// Shared application state - contains the registry of Gaia nodes and the event journal
 pub struct AppState {
-    pub node_manager: Arc<GaiaNodeManager>,
+    pub nodes: NodeRegistry,
+    pub journal: EventJournal,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct StartNodeRequest {
+    /// Task the node belongs to, defaults to the manually managed local node
+    pub task_id: Option<u32>,
     pub network: Option<String>,
     pub data_dir: Option<String>,
+    pub verbose: Option<bool>,
+    pub port: Option<u16>,
+}
+
+/// Selects the node a request targets, defaults to the manually managed local node
+#[derive(Serialize, Deserialize)]
+pub struct NodeQuery {
+    pub task_id: Option<u32>,
+}
+
+impl NodeQuery {
+    fn task_id(&self) -> u32 {
+        self.task_id.unwrap_or(LOCAL_TASK_ID)
+    }
+}
+
+/// Selects the node and the part of its log a request reads
+#[derive(Serialize, Deserialize)]
+pub struct LogsQuery {
+    pub task_id: Option<u32>,
+    /// Only the last `tail` lines
+    pub tail: Option<usize>,
+    /// Only lines read at or after this unix timestamp in seconds
+    pub since: Option<u64>,
+}
+
+impl LogsQuery {
+    fn task_id(&self) -> u32 {
+        self.task_id.unwrap_or(LOCAL_TASK_ID)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NodeSummary {
+    pub task_id: u32,
+    pub status: GaiaNodeStatus,
+}
+
+/// Latest resource usage sample of a node and the buffered history, oldest first
+#[derive(Serialize, Deserialize)]
+pub struct NodeResources {
+    pub task_id: u32,
+    pub current: Option<ResourceUsage>,
+    pub history: Vec<ResourceUsage>,
+}
+
+fn node_not_found(task_id: u32) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": format!("No node is registered for task {}", task_id)
+    }))
 }
 
 #[get("/status")]
-async fn get_status(data: web::Data<AppState>) -> impl Responder {
-    let status = data.node_manager.get_status().await;
+async fn get_status(data: web::Data<AppState>, query: web::Query<NodeQuery>) -> impl Responder {
+    let status = match data.nodes.get(query.task_id()).await {
+        Some(node) => node.get_status().await,
+        None => GaiaNodeStatus::Stopped,
+    };
     HttpResponse::Ok().json(status)
 }
 
-// #[get("/info")]
-// async fn get_info(data: web::Data<AppState>) -> impl Responder {
-//     match data.node_manager.get_info().await {
-//         Ok(info) => HttpResponse::Ok().json(info),
-//         Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-//             "error": format!("Failed to get node info: {}", e)
-//         })),
-//     }
-// }
+#[get("/nodes")]
+async fn list_nodes(data: web::Data<AppState>) -> impl Responder {
+    let nodes: Vec<NodeSummary> = data
+        .nodes
+        .statuses()
+        .await
+        .into_iter()
+        .map(|(task_id, status)| NodeSummary { task_id, status })
+        .collect();
+    HttpResponse::Ok().json(nodes)
+}
+
+#[get("/info")]
+async fn get_info(data: web::Data<AppState>, query: web::Query<NodeQuery>) -> impl Responder {
+    let Some(node) = data.nodes.get(query.task_id()).await else {
+        return node_not_found(query.task_id());
+    };
+
+    match node.get_info().await {
+        Ok(info) => HttpResponse::Ok().json(info),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to get node info: {}", e)
+        })),
+    }
+}
+
+/// Metrics of the service in the OpenMetrics text format, for Prometheus to scrape
+#[get("/metrics")]
+async fn get_metrics(data: web::Data<AppState>) -> impl Responder {
+    // node states are read at scrape time so removed nodes disappear from the output
+    METRICS.set_node_statuses(&data.nodes.statuses().await);
+    HttpResponse::Ok()
+        .content_type(metrics::CONTENT_TYPE)
+        .body(METRICS.encode())
+}
+
+/// CPU, memory, open files and disk usage of the node, sampled periodically
+#[get("/metrics/node")]
+async fn get_node_metrics(
+    data: web::Data<AppState>,
+    query: web::Query<NodeQuery>,
+) -> impl Responder {
+    let Some(node) = data.nodes.get(query.task_id()).await else {
+        return node_not_found(query.task_id());
+    };
+
+    HttpResponse::Ok().json(NodeResources {
+        task_id: query.task_id(),
+        current: node.metrics().latest(),
+        history: node.metrics().history(),
+    })
+}
+
+/// Lists the on-chain events applied by the jobs, optionally only those of one task
+#[get("/journal")]
+async fn get_journal(data: web::Data<AppState>, query: web::Query<NodeQuery>) -> impl Responder {
+    let entries: Vec<_> = data
+        .journal
+        .entries()
+        .await
+        .into_iter()
+        .filter(|entry| {
+            query
+                .task_id
+                .map_or(true, |task_id| entry.key.task_id == task_id)
+        })
+        .collect();
+    HttpResponse::Ok().json(entries)
+}
+
+/// Lines recently written by the node process
+#[get("/logs")]
+async fn get_logs(data: web::Data<AppState>, query: web::Query<LogsQuery>) -> impl Responder {
+    let Some(node) = data.nodes.get(query.task_id()).await else {
+        return node_not_found(query.task_id());
+    };
+
+    HttpResponse::Ok().json(node.logs().lines(query.tail, query.since))
+}
+
+/// Streams the node's output as Server-Sent Events, starting with the buffered lines selected
+/// by `tail`/`since` (none by default). Every event carries the line's `seq` as its id.
+#[get("/logs/stream")]
+async fn stream_logs(data: web::Data<AppState>, query: web::Query<LogsQuery>) -> impl Responder {
+    let Some(node) = data.nodes.get(query.task_id()).await else {
+        return node_not_found(query.task_id());
+    };
+
+    // subscribe before reading the buffer so no line falls between the two
+    let receiver = node.logs().subscribe();
+    let tail = match (query.tail, query.since) {
+        (None, None) => Some(0),
+        (tail, _) => tail,
+    };
+    let backlog = node.logs().lines(tail, query.since);
+    let last_seq = backlog.last().map(|line| line.seq);
+
+    let live = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(line) => return Some((line, receiver)),
+                // the client is too slow, skip what it missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .filter(move |line| std::future::ready(last_seq.map_or(true, |seq| line.seq > seq)));
+
+    let events = futures::stream::iter(backlog)
+        .chain(live)
+        .map(|line| Ok::<_, actix_web::Error>(sse_event(&line)));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+fn sse_event(line: &LogLine) -> web::Bytes {
+    let data = serde_json::to_string(line).unwrap_or_default();
+    web::Bytes::from(format!("id: {}\nevent: log\ndata: {}\n\n", line.seq, data))
+}
 
 #[post("/start")]
 async fn start_node(data: web::Data<AppState>, req: web::Json<StartNodeRequest>) -> impl Responder {
@@ -43,10 +219,20 @@ async fn start_node(data: web::Data<AppState>, req: web::Json<StartNodeRequest>)
         config.data_dir = data_dir.clone();
     }
 
-    match data.node_manager.start(config).await {
+    if let Some(verbose) = req.verbose {
+        config.verbose = verbose;
+    }
+
+    config.port = req.port;
+
+    let task_id = req.task_id.unwrap_or(LOCAL_TASK_ID);
+    match data.nodes.start_node(task_id, config).await {
         Ok(_) => HttpResponse::Ok().json(serde_json::json!({
-            "status": "started",
-            "message": "GaiaNet node started successfully"
+            "status": "starting",
+            "message": "GaiaNet node is starting, poll /status until it is Running"
+        })),
+        Err(e @ GaiaError::InvalidState(_)) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Failed to start node: {}", e)
         })),
         Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
             "error": format!("Failed to start node: {}", e)
@@ -55,8 +241,12 @@ async fn start_node(data: web::Data<AppState>, req: web::Json<StartNodeRequest>)
 }
 
 #[post("/stop")]
-async fn stop_node(data: web::Data<AppState>) -> impl Responder {
-    match data.node_manager.stop().await {
+async fn stop_node(data: web::Data<AppState>, query: web::Query<NodeQuery>) -> impl Responder {
+    if data.nodes.get(query.task_id()).await.is_none() {
+        return node_not_found(query.task_id());
+    }
+
+    match data.nodes.stop_node(query.task_id()).await {
         Ok(_) => HttpResponse::Ok().json(serde_json::json!({
             "status": "stopped",
             "message": "GaiaNet node stopped successfully"
@@ -68,22 +258,51 @@ async fn stop_node(data: web::Data<AppState>) -> impl Responder {
 }
 
 pub async fn run_server(
-    node_manager: Arc<GaiaNodeManager>,
+    nodes: NodeRegistry,
+    journal: EventJournal,
     bind_address: &str,
 ) -> std::io::Result<()> {
     blueprint_sdk::logging::info!("Starting Gaia Node API server on {}", bind_address);
 
-    HttpServer::new(move || {
+    // build the server in its own statement so the non-Send builder isn't held across the
+    // await and the server can be spawned onto the blueprint's runtime
+    let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(AppState {
-                node_manager: Arc::clone(&node_manager),
+                nodes: nodes.clone(),
+                journal: journal.clone(),
             }))
+            // requests are labelled by route pattern so query strings and ids don't add series
+            .wrap_fn(|req, srv| {
+                let started = Instant::now();
+                let method = req.method().to_string();
+                let path = req
+                    .match_pattern()
+                    .unwrap_or_else(|| "unmatched".to_string());
+                let response = srv.call(req);
+                async move {
+                    let response = response.await;
+                    let status = match &response {
+                        Ok(response) => response.status(),
+                        Err(e) => e.as_response_error().status_code(),
+                    };
+                    METRICS.http_request(&method, &path, status.as_u16(), started.elapsed());
+                    response
+                }
+            })
             .service(get_status)
+            .service(list_nodes)
             .service(start_node)
             .service(stop_node)
-        // .service(get_info)
+            .service(get_info)
+            .service(get_metrics)
+            .service(get_node_metrics)
+            .service(get_journal)
+            .service(get_logs)
+            .service(stream_logs)
     })
     .bind(bind_address)?
-    .run()
-    .await
+    .run();
+
+    server.await
 }
@@ -0,0 +1,459 @@
+use crate::attestation::{task_response_digest, SignedTaskResponse, TaskResponse};
+use crate::IBLSSignatureChecker::NonSignerStakesAndSignature;
+use crate::TangleTaskManager;
+use crate::BN254::{G1Point, G2Point};
+use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use anyhow::{anyhow, Result};
+use blueprint_sdk::alloy::network::EthereumWallet;
+use blueprint_sdk::alloy::primitives::{Address, B256};
+use blueprint_sdk::alloy::providers::Provider;
+use blueprint_sdk::alloy::signers::local::PrivateKeySigner;
+use blueprint_sdk::eigensdk::client_avsregistry::reader::AvsRegistryChainReader;
+use blueprint_sdk::eigensdk::crypto_bls::{convert_to_g1_point, convert_to_g2_point};
+use blueprint_sdk::eigensdk::logging::logger::Logger;
+use blueprint_sdk::eigensdk::logging::SharedLogger;
+use blueprint_sdk::eigensdk::services_avsregistry::chaincaller::AvsRegistryServiceChainCaller;
+use blueprint_sdk::eigensdk::services_blsaggregation::bls_agg::{
+    BlsAggregationServiceResponse, BlsAggregatorService,
+};
+use blueprint_sdk::eigensdk::services_operatorsinfo::operatorsinfo_inmemory::OperatorInfoServiceInMemory;
+use blueprint_sdk::logging::{debug, error, info, warn};
+use blueprint_sdk::utils::evm::{get_provider_http, get_wallet_provider_http};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OnceCell};
+use tokio_util::sync::CancellationToken;
+
+type AggregatorService = BlsAggregatorService<
+    AvsRegistryServiceChainCaller<AvsRegistryChainReader, OperatorInfoServiceInMemory>,
+>;
+
+/// Hands the log lines of the eigensdk clients to the service's own logger
+#[derive(Debug)]
+struct ServiceLogger;
+
+impl Logger for ServiceLogger {
+    fn debug(&self, msg: &str, tags: &str) {
+        debug!(tags, "{}", msg);
+    }
+
+    fn info(&self, msg: &str, tags: &str) {
+        info!(tags, "{}", msg);
+    }
+
+    fn warn(&self, msg: &str, tags: &str) {
+        warn!(tags, "{}", msg);
+    }
+
+    fn error(&self, msg: &str, tags: &str) {
+        error!(tags, "{}", msg);
+    }
+
+    fn fatal(&self, msg: &str, tags: &str) {
+        error!(tags, "{}", msg);
+    }
+
+    fn log(&self, msg: &str) {
+        info!("{}", msg);
+    }
+}
+
+/// Settings of the aggregator, see the `aggregator` binary for how they are provided
+#[derive(Debug, Clone)]
+pub struct AggregatorConfig {
+    pub http_rpc_url: String,
+    pub ws_rpc_url: String,
+    pub bind_address: String,
+    pub task_manager_address: Address,
+    pub registry_coordinator_address: Address,
+    pub operator_state_retriever_address: Address,
+    /// Key of the account registered as `aggregator` on the task manager
+    pub private_key: String,
+    /// Average block time of the chain, turns the task response window into a deadline
+    pub block_time: Duration,
+}
+
+/// An attestation round collects signatures over one task response
+struct Round {
+    index: u32,
+    responses: HashMap<B256, TaskResponse>,
+    // set once the aggregation service knows about the round
+    initialized: Arc<OnceCell<()>>,
+    // end of the on-chain response window of the reference block, the aggregation service
+    // drops the round from then on
+    expires_at: Instant,
+}
+
+/// Collects operators' signed task responses, aggregates their BLS signatures once the quorum
+/// threshold is met and submits the aggregate to `TangleTaskManager.respondToTask`
+#[derive(Clone)]
+pub struct Aggregator {
+    config: AggregatorConfig,
+    service: Arc<AggregatorService>,
+    // rounds keyed by (task id, reference block)
+    rounds: Arc<Mutex<HashMap<(u32, u32), Round>>>,
+    next_round: Arc<AtomicU32>,
+    quorum_numbers: Vec<u8>,
+    quorum_threshold_percentages: Vec<u8>,
+    // blocks after the reference block within which the contract accepts a response
+    window_blocks: u32,
+}
+
+impl Aggregator {
+    pub async fn new(config: AggregatorConfig) -> Result<Self> {
+        let logger: SharedLogger = Arc::new(ServiceLogger);
+        let avs_registry_reader = AvsRegistryChainReader::new(
+            logger.clone(),
+            config.registry_coordinator_address,
+            config.operator_state_retriever_address,
+            config.http_rpc_url.clone(),
+        )
+        .await?;
+
+        let operators_info = OperatorInfoServiceInMemory::new(
+            logger,
+            avs_registry_reader.clone(),
+            config.ws_rpc_url.clone(),
+        )
+        .await?
+        .0;
+
+        // keep the operator pubkeys in sync with registrations from here on
+        let current_block = get_provider_http(&config.http_rpc_url)
+            .get_block_number()
+            .await?;
+        let token = CancellationToken::new();
+        {
+            let operators_info = operators_info.clone();
+            tokio::spawn(async move {
+                if let Err(e) = operators_info.start_service(&token, 0, current_block).await {
+                    error!("Operator info service stopped: {:?}", e);
+                }
+            });
+        }
+
+        let avs_registry_service =
+            AvsRegistryServiceChainCaller::new(avs_registry_reader, operators_info);
+        let service = BlsAggregatorService::new(avs_registry_service);
+
+        let contract = TangleTaskManager::new(
+            config.task_manager_address,
+            get_provider_http(&config.http_rpc_url),
+        );
+        let quorum_numbers = contract.QUORUM_NUMBERS().call().await?._0.to_vec();
+        let threshold = u8::try_from(contract.QUORUM_THRESHOLD_PERCENTAGE().call().await?._0)?;
+        let window_blocks = contract.TASK_RESPONSE_WINDOW_BLOCK().call().await?._0;
+
+        Ok(Self {
+            service: Arc::new(service),
+            rounds: Arc::new(Mutex::new(HashMap::new())),
+            next_round: Arc::new(AtomicU32::new(0)),
+            quorum_threshold_percentages: vec![threshold; quorum_numbers.len()],
+            quorum_numbers,
+            window_blocks,
+            config,
+        })
+    }
+
+    /// Serves the HTTP endpoint operators post their signed responses to and submits
+    /// aggregated responses until the process stops
+    pub async fn run(self) -> Result<()> {
+        {
+            let aggregator = self.clone();
+            tokio::spawn(async move { aggregator.submit_aggregated_responses().await });
+        }
+
+        info!("Starting aggregator on {}", self.config.bind_address);
+        let bind_address = self.config.bind_address.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(self.clone()))
+                .service(receive_response)
+                .service(list_responses)
+        })
+        .bind(bind_address)?
+        .run();
+
+        server.await?;
+        Ok(())
+    }
+
+    /// Verifies a signed response and feeds its signature into the aggregation service. Only
+    /// responses whose signature checks out are recorded and offered to other operators.
+    pub async fn process_signed_response(&self, signed: SignedTaskResponse) -> Result<()> {
+        let SignedTaskResponse {
+            task_response: response,
+            signature,
+            operator_id,
+        } = signed;
+        let digest = task_response_digest(&response);
+        let key = (response.referenceTaskIndex, response.referenceBlockNumber);
+
+        self.expire_rounds().await;
+        let time_to_expiry = self.time_to_expiry(response.referenceBlockNumber).await?;
+        let expires_at = Instant::now() + time_to_expiry;
+        // the round is reserved under the lock and initialized after releasing it, signatures
+        // arriving meanwhile wait for the initialization instead of blocking every other round
+        let (round_index, initialized, created) = {
+            let mut rounds = self.rounds.lock().await;
+            match rounds.entry(key) {
+                Entry::Occupied(entry) => {
+                    (entry.get().index, entry.get().initialized.clone(), false)
+                }
+                Entry::Vacant(entry) => {
+                    let round = entry.insert(Round {
+                        index: self.next_round.fetch_add(1, Ordering::SeqCst) + 1,
+                        responses: HashMap::new(),
+                        initialized: Arc::new(OnceCell::new()),
+                        expires_at,
+                    });
+                    (round.index, round.initialized.clone(), true)
+                }
+            }
+        };
+
+        // the service checks the operator id and the signature against the operator's key
+        let verified = async {
+            initialized
+                .get_or_try_init(|| {
+                    self.service.initialize_new_task(
+                        round_index,
+                        response.referenceBlockNumber,
+                        self.quorum_numbers.clone(),
+                        self.quorum_threshold_percentages.clone(),
+                        time_to_expiry,
+                    )
+                })
+                .await?;
+            self.service
+                .process_new_signature(round_index, digest, signature, operator_id)
+                .await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+
+        let mut rounds = self.rounds.lock().await;
+        if let Err(e) = verified {
+            // don't keep a round around that no valid signature made it into
+            if created
+                && rounds
+                    .get(&key)
+                    .is_some_and(|round| round.index == round_index && round.responses.is_empty())
+            {
+                rounds.remove(&key);
+            }
+            return Err(e);
+        }
+
+        // a failed signature may have dropped the round while this one was being verified
+        let round = rounds.entry(key).or_insert_with(|| Round {
+            index: round_index,
+            responses: HashMap::new(),
+            initialized,
+            expires_at,
+        });
+        if round.index != round_index {
+            return Err(anyhow!(
+                "round of task {} at block {} was replaced during verification",
+                key.0,
+                key.1
+            ));
+        }
+        round.responses.insert(digest, response);
+
+        Ok(())
+    }
+
+    /// Time left until the contract stops accepting responses referencing `reference_block`
+    async fn time_to_expiry(&self, reference_block: u32) -> Result<Duration> {
+        let current_block = get_provider_http(&self.config.http_rpc_url)
+            .get_block_number()
+            .await?;
+        let reference_block = u64::from(reference_block);
+        if reference_block > current_block {
+            return Err(anyhow!(
+                "reference block {} is ahead of the chain at block {}",
+                reference_block,
+                current_block
+            ));
+        }
+        let deadline = reference_block + u64::from(self.window_blocks);
+        if deadline <= current_block {
+            return Err(anyhow!(
+                "response window of block {} closed at block {}",
+                reference_block,
+                deadline
+            ));
+        }
+        Ok(self.config.block_time * u32::try_from(deadline - current_block)?)
+    }
+
+    async fn submit_aggregated_responses(&self) {
+        let signer: PrivateKeySigner = match self.config.private_key.parse() {
+            Ok(signer) => signer,
+            Err(e) => {
+                error!("Invalid aggregator private key: {}", e);
+                return;
+            }
+        };
+        let provider =
+            get_wallet_provider_http(&self.config.http_rpc_url, EthereumWallet::from(signer));
+        let contract = TangleTaskManager::new(self.config.task_manager_address, provider);
+
+        let mut receiver = self.service.aggregated_response_receiver.lock().await;
+        while let Some(result) = receiver.recv().await {
+            let aggregated = match result {
+                Ok(aggregated) => aggregated,
+                Err(e) => {
+                    warn!("Attestation round did not reach quorum: {:?}", e);
+                    // the error doesn't name its round, but a round only fails once its
+                    // response window passed
+                    self.expire_rounds().await;
+                    continue;
+                }
+            };
+
+            let Some(task_response) = self.take_response(&aggregated).await else {
+                warn!(
+                    "No task response recorded for aggregated round {}",
+                    aggregated.task_index
+                );
+                continue;
+            };
+            let task_id = task_response.referenceTaskIndex;
+
+            let signature = match non_signer_stakes_and_signature(aggregated) {
+                Ok(signature) => signature,
+                Err(e) => {
+                    error!("Failed to encode aggregate for task {}: {}", task_id, e);
+                    continue;
+                }
+            };
+
+            match contract
+                .respondToTask(task_response, signature)
+                .send()
+                .await
+            {
+                Ok(pending) => match pending.get_receipt().await {
+                    Ok(receipt) => info!(
+                        "Submitted aggregated response for task {} in {:?}",
+                        task_id, receipt.transaction_hash
+                    ),
+                    Err(e) => error!("Response for task {} was not mined: {}", task_id, e),
+                },
+                Err(e) => error!("Failed to submit response for task {}: {}", task_id, e),
+            }
+        }
+    }
+
+    /// Responses of the rounds still collecting signatures, for operators to verify and sign
+    pub async fn pending_responses(&self) -> Vec<TaskResponse> {
+        self.expire_rounds().await;
+        self.rounds
+            .lock()
+            .await
+            .values()
+            .flat_map(|round| round.responses.values().cloned())
+            .collect()
+    }
+
+    /// Forgets the rounds whose response window passed
+    async fn expire_rounds(&self) {
+        let now = Instant::now();
+        self.rounds.lock().await.retain(|(task_id, _), round| {
+            let expired = round.expires_at <= now;
+            if expired {
+                debug!(
+                    "Dropping attestation round {} of task {}",
+                    round.index, task_id
+                );
+            }
+            !expired
+        });
+    }
+
+    /// Removes the round an aggregate belongs to and returns the response that was signed
+    async fn take_response(
+        &self,
+        aggregated: &BlsAggregationServiceResponse,
+    ) -> Option<TaskResponse> {
+        let mut rounds = self.rounds.lock().await;
+        let key = rounds
+            .iter()
+            .find(|(_, round)| round.index == aggregated.task_index)
+            .map(|(key, _)| *key)?;
+        rounds
+            .remove(&key)?
+            .responses
+            .remove(&aggregated.task_response_digest)
+    }
+}
+
+/// Converts the aggregation service output into the contract's signature check input
+fn non_signer_stakes_and_signature(
+    aggregated: BlsAggregationServiceResponse,
+) -> Result<NonSignerStakesAndSignature> {
+    let g1 = |point| -> Result<G1Point> {
+        let point = convert_to_g1_point(point).map_err(|e| anyhow!("{:?}", e))?;
+        Ok(G1Point {
+            X: point.X,
+            Y: point.Y,
+        })
+    };
+
+    let non_signer_pubkeys = aggregated
+        .non_signers_pub_keys_g1
+        .into_iter()
+        .map(|pub_key| g1(pub_key.g1()))
+        .collect::<Result<Vec<_>>>()?;
+    let quorum_apks = aggregated
+        .quorum_apks_g1
+        .into_iter()
+        .map(|apk| g1(apk.g1()))
+        .collect::<Result<Vec<_>>>()?;
+    let apk_g2 =
+        convert_to_g2_point(aggregated.signers_apk_g2.g2()).map_err(|e| anyhow!("{:?}", e))?;
+
+    Ok(NonSignerStakesAndSignature {
+        nonSignerQuorumBitmapIndices: aggregated.non_signer_quorum_bitmap_indices,
+        nonSignerPubkeys: non_signer_pubkeys,
+        quorumApks: quorum_apks,
+        apkG2: G2Point {
+            X: apk_g2.X,
+            Y: apk_g2.Y,
+        },
+        sigma: g1(aggregated.signers_agg_sig_g1.g1_point().g1())?,
+        quorumApkIndices: aggregated.quorum_apk_indices,
+        totalStakeIndices: aggregated.total_stake_indices,
+        nonSignerStakeIndices: aggregated.non_signer_stake_indices,
+    })
+}
+
+#[post("/responses")]
+async fn receive_response(
+    aggregator: web::Data<Aggregator>,
+    signed: web::Json<SignedTaskResponse>,
+) -> impl Responder {
+    let task_id = signed.task_response.referenceTaskIndex;
+    match aggregator
+        .process_signed_response(signed.into_inner())
+        .await
+    {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "accepted",
+            "task_id": task_id
+        })),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Failed to process signed response: {}", e)
+        })),
+    }
+}
+
+#[get("/responses")]
+async fn list_responses(aggregator: web::Data<Aggregator>) -> impl Responder {
+    HttpResponse::Ok().json(aggregator.pending_responses().await)
+}
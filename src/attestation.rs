@@ -0,0 +1,300 @@
+use crate::node_registry::LOCAL_TASK_ID;
+use crate::types::{public_node_url, GaiaNodeStatus};
+use crate::{ExampleContext, TangleTaskManager, TASK_MANAGER_ADDRESS};
+use anyhow::{anyhow, Result};
+use blueprint_sdk::alloy::primitives::{keccak256, FixedBytes, B256};
+use blueprint_sdk::alloy::providers::Provider;
+use blueprint_sdk::alloy::rpc::types::BlockId;
+use blueprint_sdk::alloy::sol_types::SolValue;
+use blueprint_sdk::config::GadgetConfiguration;
+use blueprint_sdk::crypto::bn254::ArkBlsBn254;
+use blueprint_sdk::eigensdk::crypto_bls::{BlsKeyPair, OperatorId, Signature};
+use blueprint_sdk::eigensdk::types::operator::operator_id_from_g1_pub_key;
+use blueprint_sdk::keystore::backends::bn254::Bn254Backend;
+use blueprint_sdk::keystore::backends::Backend;
+use blueprint_sdk::logging::{error, info, warn};
+use blueprint_sdk::utils::evm::get_provider_http;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use url::Url;
+
+/// Default delay between two liveness attestation rounds
+pub const DEFAULT_ATTESTATION_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How long a node's public endpoint gets to answer a liveness probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Liveness attestation for a Gaia node task, the struct `respondToTask` verifies signatures over
+pub use crate::ITangleTaskManager::TaskResponse;
+
+/// A task response together with the operator's BLS signature over its digest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTaskResponse {
+    pub task_response: TaskResponse,
+    pub signature: Signature,
+    pub operator_id: FixedBytes<32>,
+}
+
+/// Digest operators sign, `keccak256(abi.encode(taskResponse))` on the contract side
+pub fn task_response_digest(task_response: &TaskResponse) -> B256 {
+    keccak256(task_response.abi_encode())
+}
+
+/// The operator's BLS key loaded from the keystore
+pub struct OperatorBlsKey {
+    key_pair: BlsKeyPair,
+    pub operator_id: OperatorId,
+}
+
+impl OperatorBlsKey {
+    pub fn from_config(config: &GadgetConfiguration) -> Result<Self> {
+        let keystore = config.keystore();
+        let public = keystore.first_local::<ArkBlsBn254>()?;
+        let secret = keystore
+            .expose_bls_bn254_secret(&public)?
+            .ok_or_else(|| anyhow!("BLS secret key is missing from the keystore"))?;
+        let key_pair = BlsKeyPair::new(secret.0.to_string())?;
+        let operator_id = operator_id_from_g1_pub_key(key_pair.public_key())?;
+
+        Ok(Self {
+            key_pair,
+            operator_id,
+        })
+    }
+
+    pub fn sign(&self, task_response: TaskResponse) -> SignedTaskResponse {
+        let digest = task_response_digest(&task_response);
+        SignedTaskResponse {
+            signature: self.key_pair.sign_message(digest.as_ref()),
+            operator_id: self.operator_id,
+            task_response,
+        }
+    }
+}
+
+/// Periodically attests the liveness of the task nodes. Our own running nodes are proposed to
+/// the aggregator at `AGGREGATOR_URL`, and every response pending there, ours included, is
+/// checked against the chain and the node's public endpoint before it is signed. All fields
+/// of a response can be verified by any operator, so the quorum signs one and the same digest.
+pub async fn run(context: ExampleContext, interval: Duration) {
+    let bls_key = match OperatorBlsKey::from_config(&context.config) {
+        Ok(bls_key) => bls_key,
+        Err(e) => {
+            error!(
+                "Liveness attestations disabled, failed to load BLS key: {}",
+                e
+            );
+            return;
+        }
+    };
+    let aggregator_url = std::env::var("AGGREGATOR_URL").ok();
+    if aggregator_url.is_none() {
+        warn!("AGGREGATOR_URL is not set, liveness attestations will only be logged");
+    }
+    let client = reqwest::Client::new();
+    // digests already signed, kept while their round is pending so they aren't signed twice
+    let mut signed = HashSet::new();
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        let proposals = match propose(&context).await {
+            Ok(proposals) => proposals,
+            Err(e) => {
+                error!("Failed to propose liveness attestations: {}", e);
+                Vec::new()
+            }
+        };
+        let pending = match &aggregator_url {
+            Some(aggregator_url) => match pending_responses(&client, aggregator_url).await {
+                Ok(pending) => pending,
+                Err(e) => {
+                    warn!("Failed to fetch pending liveness attestations: {}", e);
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        let pending_digests: HashSet<B256> = pending.iter().map(task_response_digest).collect();
+        signed.retain(|digest| pending_digests.contains(digest));
+
+        for task_response in proposals.into_iter().chain(pending) {
+            let digest = task_response_digest(&task_response);
+            if signed.contains(&digest) {
+                continue;
+            }
+            let task_id = task_response.referenceTaskIndex;
+            if let Err(e) = verify(&context, &client, &task_response).await {
+                warn!(
+                    "Not signing liveness attestation for task {}: {}",
+                    task_id, e
+                );
+                continue;
+            }
+
+            let response = bls_key.sign(task_response);
+            let Some(aggregator_url) = &aggregator_url else {
+                info!("Signed liveness attestation: {:?}", response);
+                continue;
+            };
+            match submit(&client, aggregator_url, &response).await {
+                Ok(()) => {
+                    signed.insert(digest);
+                    info!("Submitted liveness attestation for task {}", task_id);
+                }
+                Err(e) => error!(
+                    "Failed to submit liveness attestation for task {}: {}",
+                    task_id, e
+                ),
+            }
+        }
+    }
+}
+
+/// Builds an unsigned liveness attestation for every task node running locally, referencing
+/// the latest block
+pub async fn propose(context: &ExampleContext) -> Result<Vec<TaskResponse>> {
+    let provider = get_provider_http(&context.config.http_rpc_endpoint);
+    let reference_block = u32::try_from(provider.get_block_number().await?)?;
+    let contract = TangleTaskManager::new(*TASK_MANAGER_ADDRESS, provider);
+
+    let mut proposals = Vec::new();
+    for (task_id, status) in context.nodes.statuses().await {
+        if task_id == LOCAL_TASK_ID || status != GaiaNodeStatus::Running {
+            continue;
+        }
+        let Some(node) = context.nodes.get(task_id).await else {
+            continue;
+        };
+
+        let mut models = match node.served_models().await {
+            Ok(models) => models,
+            Err(e) => {
+                warn!("Failed to list models of task {}: {}", task_id, e);
+                continue;
+            }
+        };
+        models.sort();
+        let endpoint = match node.get_info().await {
+            Ok(info) => public_node_url(&info.node_id),
+            Err(e) => {
+                warn!("Failed to read node id of task {}: {}", task_id, e);
+                continue;
+            }
+        };
+        let status = match contract
+            .getGaiaNodeStatus(task_id)
+            .block(BlockId::number(reference_block.into()))
+            .call()
+            .await
+        {
+            Ok(status) => status._0,
+            Err(e) => {
+                warn!("Failed to read chain status of task {}: {}", task_id, e);
+                continue;
+            }
+        };
+
+        proposals.push(TaskResponse {
+            referenceTaskIndex: task_id,
+            referenceBlockNumber: reference_block,
+            isRunning: true,
+            // time since the task started as of the reference block, the same for every signer
+            uptime: status.uptime,
+            model: models.into_iter().next().unwrap_or_default(),
+            endpoint,
+        });
+    }
+
+    Ok(proposals)
+}
+
+/// Checks a liveness attestation the way every operator can: the task must be running on
+/// chain with the claimed uptime at the reference block, and its public endpoint must serve
+/// the claimed model
+pub async fn verify(
+    context: &ExampleContext,
+    client: &reqwest::Client,
+    task_response: &TaskResponse,
+) -> Result<()> {
+    let contract = TangleTaskManager::new(
+        *TASK_MANAGER_ADDRESS,
+        get_provider_http(&context.config.http_rpc_endpoint),
+    );
+    let status = contract
+        .getGaiaNodeStatus(task_response.referenceTaskIndex)
+        .block(BlockId::number(task_response.referenceBlockNumber.into()))
+        .call()
+        .await?
+        ._0;
+    if !task_response.isRunning || !status.isRunning {
+        return Err(anyhow!("the task is not running on chain"));
+    }
+    if task_response.uptime != status.uptime {
+        return Err(anyhow!(
+            "uptime {} differs from the {} seconds recorded on chain",
+            task_response.uptime,
+            status.uptime
+        ));
+    }
+
+    // only Gaia domains are probed, a response can't make operators call arbitrary hosts
+    let endpoint = Url::parse(&task_response.endpoint)?;
+    if endpoint.scheme() != "https"
+        || !endpoint
+            .host_str()
+            .is_some_and(|host| host.ends_with(".gaia.domains"))
+    {
+        return Err(anyhow!(
+            "{} is not a Gaia domain endpoint",
+            task_response.endpoint
+        ));
+    }
+
+    let url = format!("{}/models", task_response.endpoint.trim_end_matches('/'));
+    let reply = client.get(&url).timeout(PROBE_TIMEOUT).send().await?;
+    if !reply.status().is_success() {
+        return Err(anyhow!("GET {} returned {}", url, reply.status()));
+    }
+    let models: serde_json::Value = reply.json().await?;
+    let serves_model = models["data"].as_array().is_some_and(|models| {
+        models
+            .iter()
+            .any(|model| model["id"].as_str() == Some(task_response.model.as_str()))
+    });
+    if !serves_model {
+        return Err(anyhow!("{} doesn't serve {}", url, task_response.model));
+    }
+
+    Ok(())
+}
+
+/// Responses the aggregator is still collecting signatures for
+async fn pending_responses(
+    client: &reqwest::Client,
+    aggregator_url: &str,
+) -> Result<Vec<TaskResponse>> {
+    let url = format!("{}/responses", aggregator_url.trim_end_matches('/'));
+    let reply = client.get(&url).send().await?;
+    if !reply.status().is_success() {
+        return Err(anyhow!("aggregator returned {}", reply.status()));
+    }
+    Ok(reply.json().await?)
+}
+
+async fn submit(
+    client: &reqwest::Client,
+    aggregator_url: &str,
+    response: &SignedTaskResponse,
+) -> Result<()> {
+    let url = format!("{}/responses", aggregator_url.trim_end_matches('/'));
+    let reply = client.post(&url).json(response).send().await?;
+    if !reply.status().is_success() {
+        let status = reply.status();
+        let body = reply.text().await.unwrap_or_default();
+        return Err(anyhow!("aggregator returned {}: {}", status, body));
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use blueprint_sdk::logging::setup_log;
+use my_eigenlayer_avs_1::aggregator::{Aggregator, AggregatorConfig};
+use my_eigenlayer_avs_1::TASK_MANAGER_ADDRESS;
+use std::time::Duration;
+use structopt::StructOpt;
+
+/// Collects the operators' signed liveness attestations and submits the aggregates on chain
+#[derive(Debug, StructOpt)]
+#[structopt(name = "aggregator")]
+struct Opt {
+    /// HTTP RPC endpoint of the chain the task manager is deployed on
+    #[structopt(long, env = "HTTP_RPC_URL", default_value = "http://localhost:8545")]
+    http_rpc_url: String,
+
+    /// WebSocket RPC endpoint, used to follow operator registrations
+    #[structopt(long, env = "WS_RPC_URL", default_value = "ws://localhost:8546")]
+    ws_rpc_url: String,
+
+    /// Address operators post their signed responses to
+    #[structopt(
+        long,
+        env = "AGGREGATOR_BIND_ADDRESS",
+        default_value = "127.0.0.1:8081"
+    )]
+    bind_address: String,
+
+    #[structopt(long, env = "REGISTRY_COORDINATOR_ADDRESS")]
+    registry_coordinator_address: String,
+
+    #[structopt(long, env = "OPERATOR_STATE_RETRIEVER_ADDRESS")]
+    operator_state_retriever_address: String,
+
+    /// Private key of the account registered as aggregator on the task manager
+    #[structopt(long, env = "AGGREGATOR_PRIVATE_KEY", hide_env_values = true)]
+    private_key: String,
+
+    /// Average block time of the chain in seconds, used to time out attestation rounds
+    #[structopt(long, env = "BLOCK_TIME_SECS", default_value = "12")]
+    block_time_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setup_log();
+    let opt = Opt::from_args();
+
+    let config = AggregatorConfig {
+        http_rpc_url: opt.http_rpc_url,
+        ws_rpc_url: opt.ws_rpc_url,
+        bind_address: opt.bind_address,
+        task_manager_address: *TASK_MANAGER_ADDRESS,
+        registry_coordinator_address: opt.registry_coordinator_address.parse()?,
+        operator_state_retriever_address: opt.operator_state_retriever_address.parse()?,
+        private_key: opt.private_key,
+        block_time: Duration::from_secs(opt.block_time_secs),
+    };
+
+    Aggregator::new(config).await?.run().await?;
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use blueprint_sdk::logging::setup_log;
+use my_eigenlayer_avs_1::challenger::{Challenger, ChallengerConfig};
+use my_eigenlayer_avs_1::types::state_dir;
+use my_eigenlayer_avs_1::TASK_MANAGER_ADDRESS;
+use std::path::PathBuf;
+use std::time::Duration;
+use structopt::StructOpt;
+
+/// Verifies the liveness claims in aggregated task responses and prepares challenge evidence
+#[derive(Debug, StructOpt)]
+#[structopt(name = "challenger")]
+struct Opt {
+    /// HTTP RPC endpoint of the chain the task manager is deployed on
+    #[structopt(long, env = "HTTP_RPC_URL", default_value = "http://localhost:8545")]
+    http_rpc_url: String,
+
+    /// Trusted OpenAI-compatible endpoint used as the embedding reference
+    #[structopt(long, env = "CHALLENGER_REFERENCE_URL")]
+    reference_url: Option<String>,
+
+    /// Embedding model requested from the nodes and the reference
+    #[structopt(
+        long,
+        env = "CHALLENGER_EMBEDDING_MODEL",
+        default_value = "nomic-embed"
+    )]
+    embedding_model: String,
+
+    /// Minimum cosine similarity between node and reference embeddings
+    #[structopt(long, default_value = "0.99")]
+    similarity_threshold: f32,
+
+    /// Where challenge evidence is written, defaults to `challenges` in the state dir
+    #[structopt(long, env = "CHALLENGER_EVIDENCE_DIR", parse(from_os_str))]
+    evidence_dir: Option<PathBuf>,
+
+    /// Seconds between two polls for new task responses
+    #[structopt(long, default_value = "12")]
+    poll_interval_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setup_log();
+    let opt = Opt::from_args();
+
+    let config = ChallengerConfig {
+        http_rpc_url: opt.http_rpc_url,
+        task_manager_address: *TASK_MANAGER_ADDRESS,
+        reference_url: opt.reference_url,
+        embedding_model: opt.embedding_model,
+        similarity_threshold: opt.similarity_threshold,
+        evidence_dir: opt
+            .evidence_dir
+            .unwrap_or_else(|| state_dir().join("challenges")),
+        poll_interval: Duration::from_secs(opt.poll_interval_secs),
+    };
+
+    Challenger::new(config).run().await?;
+
+    Ok(())
+}
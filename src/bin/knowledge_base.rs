@@ -0,0 +1,85 @@
+use blueprint_sdk::logging::{info, setup_log};
+use my_eigenlayer_avs_1::dynamic_rag::{DynamicRAG, QdrantStore, RagConfig};
+use my_eigenlayer_avs_1::types::expand_home;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Builds knowledge bases and ships them to Gaia nodes as Qdrant snapshots. Endpoints are
+/// taken from the `RagConfig` environment variables.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "knowledge-base")]
+enum Opt {
+    /// Chunks, embeds and stores a file or a directory of documents
+    Ingest {
+        #[structopt(long)]
+        collection: String,
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+    },
+    /// Snapshots a collection into the data dir of a Gaia node
+    Export {
+        #[structopt(long)]
+        collection: String,
+        /// Data dir of the node receiving the snapshot
+        #[structopt(long, default_value = "~/.gaianet")]
+        data_dir: String,
+    },
+    /// Restores a collection from a snapshot file
+    Import {
+        #[structopt(long)]
+        collection: String,
+        #[structopt(parse(from_os_str))]
+        snapshot: PathBuf,
+        /// Expected SHA-256 of the snapshot, read from `<snapshot>.sha256` when omitted
+        #[structopt(long)]
+        checksum: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setup_log();
+    let config = RagConfig::from_env();
+
+    match Opt::from_args() {
+        Opt::Ingest { collection, path } => {
+            let rag = DynamicRAG::with_config(config)?;
+            let stored = rag.ingest_path(&collection, &path).await?;
+            info!(
+                "Stored {} chunks of {} in {}",
+                stored,
+                path.display(),
+                collection
+            );
+        }
+        Opt::Export {
+            collection,
+            data_dir,
+        } => {
+            let store = QdrantStore::from_config(&config)?;
+            let snapshot = store
+                .export_snapshot(&collection, &expand_home(&data_dir))
+                .await?;
+            info!(
+                "Exported {} to {} ({} bytes, sha256 {})",
+                collection,
+                snapshot.path.display(),
+                snapshot.size,
+                snapshot.checksum
+            );
+        }
+        Opt::Import {
+            collection,
+            snapshot,
+            checksum,
+        } => {
+            let store = QdrantStore::from_config(&config)?;
+            store
+                .import_snapshot(&collection, &snapshot, checksum.as_deref())
+                .await?;
+            info!("Restored {} from {}", collection, snapshot.display());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,302 @@
+use crate::attestation::TaskResponse;
+use crate::ITangleTaskManager::TaskResponseMetadata;
+use crate::TangleTaskManager;
+use anyhow::{anyhow, Result};
+use blueprint_sdk::alloy::primitives::Address;
+use blueprint_sdk::alloy::providers::Provider;
+use blueprint_sdk::logging::{error, info, warn};
+use blueprint_sdk::utils::evm::get_provider_http;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Text embedded by both the challenged node and the reference endpoint
+const PROBE_TEXT: &str = "Gaia nodes serve open source models through an OpenAI-compatible API.";
+
+/// Probes of a node that fail to connect or time out before its failure is recorded
+const PROBE_ATTEMPTS: u32 = 3;
+
+/// Delay before probing again after a transient failure, doubled on every attempt
+const PROBE_INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Settings of the challenger, see the `challenger` binary for how they are provided
+#[derive(Debug, Clone)]
+pub struct ChallengerConfig {
+    pub http_rpc_url: String,
+    pub task_manager_address: Address,
+    /// Trusted OpenAI-compatible endpoint serving the same embedding model as the nodes,
+    /// embeddings are only compared when it is set
+    pub reference_url: Option<String>,
+    pub embedding_model: String,
+    /// Minimum cosine similarity between the node's and the reference embedding
+    pub similarity_threshold: f32,
+    /// Directory challenge evidence is written to
+    pub evidence_dir: PathBuf,
+    pub poll_interval: Duration,
+}
+
+/// Everything needed to challenge a task response an operator lied in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeEvidence {
+    pub task_response: TaskResponse,
+    pub task_response_metadata: TaskResponseMetadata,
+    /// Checks the node failed, in plain words
+    pub failures: Vec<String>,
+    /// Models the node actually listed, empty if it could not be reached
+    pub observed_models: Vec<String>,
+    /// Cosine similarity between the node's and the reference embedding, if compared
+    pub embedding_similarity: Option<f32>,
+    /// Unix time (seconds) the node was probed at
+    pub checked_at: u64,
+}
+
+/// Watches aggregated task responses and independently probes the nodes they attest to
+pub struct Challenger {
+    config: ChallengerConfig,
+    client: reqwest::Client,
+}
+
+impl Challenger {
+    pub fn new(config: ChallengerConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Follows `TaskResponded` events until the process stops
+    pub async fn run(&self) -> Result<()> {
+        let provider = get_provider_http(&self.config.http_rpc_url);
+        let contract = TangleTaskManager::new(self.config.task_manager_address, provider.clone());
+        let challenge_window = contract.TASK_CHALLENGE_WINDOW_BLOCK().call().await?._0;
+
+        let mut from_block = provider.get_block_number().await?;
+        info!("Watching task responses from block {}", from_block);
+
+        loop {
+            tokio::time::sleep(self.config.poll_interval).await;
+
+            let latest_block = match provider.get_block_number().await {
+                Ok(block) => block,
+                Err(e) => {
+                    error!("Failed to fetch the latest block: {}", e);
+                    continue;
+                }
+            };
+            if latest_block < from_block {
+                continue;
+            }
+
+            let events = match contract
+                .TaskResponded_filter()
+                .from_block(from_block)
+                .to_block(latest_block)
+                .query()
+                .await
+            {
+                Ok(events) => events,
+                Err(e) => {
+                    error!("Failed to fetch task responses: {}", e);
+                    continue;
+                }
+            };
+            from_block = latest_block + 1;
+
+            for (event, _log) in events {
+                let response = event.taskResponse;
+                let metadata = event.taskResponseMetadata;
+                let task_id = response.referenceTaskIndex;
+
+                let deadline = u64::from(metadata.taskResponsedBlock) + u64::from(challenge_window);
+                if latest_block > deadline {
+                    warn!("Challenge window of task {} already closed", task_id);
+                    continue;
+                }
+
+                match self.check(response, metadata).await {
+                    Some(evidence) => {
+                        warn!(
+                            "Operator response for task {} is wrong: {}",
+                            task_id,
+                            evidence.failures.join("; ")
+                        );
+                        match self.record(&evidence).await {
+                            Ok(path) => info!("Challenge evidence written to {}", path.display()),
+                            Err(e) => error!("Failed to write challenge evidence: {}", e),
+                        }
+                    }
+                    None => info!("Operator response for task {} checks out", task_id),
+                }
+            }
+        }
+    }
+
+    /// Probes the node a response attests to, returning evidence if any claim is false
+    pub async fn check(
+        &self,
+        task_response: TaskResponse,
+        task_response_metadata: TaskResponseMetadata,
+    ) -> Option<ChallengeEvidence> {
+        // a node reported as down makes no claim that can be checked
+        if !task_response.isRunning {
+            return None;
+        }
+
+        let endpoint = task_response.endpoint.trim_end_matches('/').to_string();
+        let mut failures = Vec::new();
+
+        let observed_models = match self.list_models(&endpoint).await {
+            Ok(models) => models,
+            Err(e) => {
+                failures.push(format!("node claimed to run but is unreachable: {}", e));
+                Vec::new()
+            }
+        };
+        if failures.is_empty() && !observed_models.contains(&task_response.model) {
+            failures.push(format!(
+                "node does not serve the declared model {:?}, it serves {:?}",
+                task_response.model, observed_models
+            ));
+        }
+
+        let mut embedding_similarity = None;
+        if let (true, Some(reference_url)) = (failures.is_empty(), &self.config.reference_url) {
+            match self.compare_embeddings(&endpoint, reference_url).await {
+                Ok(similarity) => {
+                    embedding_similarity = Some(similarity);
+                    if similarity < self.config.similarity_threshold {
+                        failures.push(format!(
+                            "embeddings differ from the reference model (similarity {:.4})",
+                            similarity
+                        ));
+                    }
+                }
+                Err(e) => failures.push(format!("failed to compute embeddings: {}", e)),
+            }
+        }
+
+        if failures.is_empty() {
+            return None;
+        }
+
+        Some(ChallengeEvidence {
+            task_response,
+            task_response_metadata,
+            failures,
+            observed_models,
+            embedding_similarity,
+            checked_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Sends a probe, trying again with backoff while the endpoint can't be reached so a
+    /// network hiccup isn't taken for a node that is down
+    async fn send_with_retry(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> reqwest::Result<reqwest::Response> {
+        let mut attempt = 1;
+        loop {
+            match request().send().await {
+                Err(e) if attempt < PROBE_ATTEMPTS && (e.is_connect() || e.is_timeout()) => {
+                    let backoff = PROBE_INITIAL_BACKOFF * 2u32.pow(attempt - 1);
+                    warn!("Probe failed ({}), retrying in {:?}", e, backoff);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn list_models(&self, endpoint: &str) -> Result<Vec<String>> {
+        let url = format!("{}/models", endpoint);
+        let response = self
+            .send_with_retry(|| self.client.get(&url).timeout(Duration::from_secs(30)))
+            .await?
+            .error_for_status()?;
+
+        let models: serde_json::Value = response.json().await?;
+        Ok(models["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn compare_embeddings(&self, endpoint: &str, reference_url: &str) -> Result<f32> {
+        let node = self.embed(endpoint).await?;
+        let reference = self.embed(reference_url.trim_end_matches('/')).await?;
+        cosine_similarity(&node, &reference)
+    }
+
+    async fn embed(&self, endpoint: &str) -> Result<Vec<f32>> {
+        let url = format!("{}/embeddings", endpoint);
+        let body = serde_json::json!({
+            "model": self.config.embedding_model,
+            "input": [PROBE_TEXT]
+        });
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .post(&url)
+                    .timeout(Duration::from_secs(60))
+                    .json(&body)
+            })
+            .await?
+            .error_for_status()?;
+
+        let embeddings: serde_json::Value = response.json().await?;
+        serde_json::from_value(embeddings["data"][0]["embedding"].clone())
+            .map_err(|e| anyhow!("invalid embeddings response: {}", e))
+    }
+
+    async fn record(&self, evidence: &ChallengeEvidence) -> Result<PathBuf> {
+        tokio::fs::create_dir_all(&self.config.evidence_dir).await?;
+        let path = self.config.evidence_dir.join(format!(
+            "task-{}-block-{}.json",
+            evidence.task_response.referenceTaskIndex, evidence.task_response.referenceBlockNumber
+        ));
+        tokio::fs::write(&path, serde_json::to_vec_pretty(evidence)?).await?;
+        Ok(path)
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> Result<f32> {
+    if a.len() != b.len() || a.is_empty() {
+        return Err(anyhow!(
+            "embedding dimensions differ ({} vs {})",
+            a.len(),
+            b.len()
+        ));
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return Ok(0.0);
+    }
+    Ok(dot / (norm_a * norm_b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cosine_similarity_of_edge_cases() {
+        let similarity = cosine_similarity(&[0.3, -1.2, 4.0], &[0.3, -1.2, 4.0]).unwrap();
+        assert!((similarity - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 2.0]).unwrap(), 0.0);
+        assert!(cosine_similarity(&[], &[]).is_err());
+        assert!(cosine_similarity(&[1.0], &[1.0, 0.0]).is_err());
+    }
+}
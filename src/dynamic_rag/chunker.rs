@@ -0,0 +1,404 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::LazyLock;
+
+static PARAGRAPH_BREAK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n[ \t]*\n\s*").unwrap());
+static SENTENCE_END: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"[.!?]+["')\]]*\s+|\n[ \t]*\n\s*"#).unwrap());
+static WHITESPACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+static MARKDOWN_HEADING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^#{1,6}\s").unwrap());
+static MARKDOWN_FENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(```|~~~)").unwrap());
+
+/// Rough token count of `text`, about four characters per token for English text
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Size limits shared by all strategies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkSize {
+    /// Upper bound of the estimated tokens in a chunk
+    pub max_tokens: usize,
+    /// Tokens from the end of a chunk repeated at the start of the next one
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkSize {
+    fn default() -> Self {
+        Self {
+            max_tokens: 512,
+            overlap_tokens: 64,
+        }
+    }
+}
+
+/// A piece of the chunked text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub text: String,
+    /// Byte offset of the chunk in the chunked text
+    pub offset: usize,
+}
+
+/// Splits text into chunks to embed. Chunks are trimmed slices of the input that together
+/// cover all of its non-whitespace text, consecutive chunks may overlap.
+pub trait Chunker: Send + Sync {
+    fn chunk(&self, text: &str) -> Vec<Chunk>;
+}
+
+/// Packs whole sentences into chunks
+#[derive(Debug, Clone, Default)]
+pub struct SentenceChunker {
+    pub size: ChunkSize,
+}
+
+impl Chunker for SentenceChunker {
+    fn chunk(&self, text: &str) -> Vec<Chunk> {
+        pack(
+            text,
+            split_matches(text, 0..text.len(), &SENTENCE_END),
+            self.size,
+        )
+    }
+}
+
+/// Packs whole paragraphs into chunks, splitting oversized paragraphs on sentences
+#[derive(Debug, Clone, Default)]
+pub struct ParagraphChunker {
+    pub size: ChunkSize,
+}
+
+impl Chunker for ParagraphChunker {
+    fn chunk(&self, text: &str) -> Vec<Chunk> {
+        pack(
+            text,
+            split_matches(text, 0..text.len(), &PARAGRAPH_BREAK),
+            self.size,
+        )
+    }
+}
+
+/// Keeps Markdown sections together, a chunk never spans two headings unless they fit
+/// together. Headings inside fenced code blocks are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownChunker {
+    pub size: ChunkSize,
+}
+
+impl Chunker for MarkdownChunker {
+    fn chunk(&self, text: &str) -> Vec<Chunk> {
+        let mut cuts = Vec::new();
+        let mut in_fence = false;
+        for (offset, line) in lines(text) {
+            if MARKDOWN_FENCE.is_match(line) {
+                in_fence = !in_fence;
+            } else if !in_fence && MARKDOWN_HEADING.is_match(line) {
+                cuts.push(offset);
+            }
+        }
+
+        pack(text, ranges_from_cuts(0..text.len(), cuts), self.size)
+    }
+}
+
+/// Programming languages the code chunker knows the top-level definitions of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    Go,
+    Solidity,
+}
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "rs" => Some(Language::Rust),
+            "py" => Some(Language::Python),
+            "js" | "jsx" | "mjs" | "ts" | "tsx" => Some(Language::JavaScript),
+            "go" => Some(Language::Go),
+            "sol" => Some(Language::Solidity),
+            _ => None,
+        }
+    }
+
+    /// Lines starting a top-level definition
+    fn definition(&self) -> &'static Regex {
+        static RUST: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^(pub(\([^)]*\))?\s+)?(async\s+)?(unsafe\s+)?(fn|struct|enum|impl|trait|mod|const|static|type|macro_rules!)\b").unwrap()
+        });
+        static PYTHON: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^(async\s+def|def|class)\s").unwrap());
+        static JAVASCRIPT: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^(export\s+)?(default\s+)?(async\s+)?(function|class|const|let|var|interface|type|enum)\b").unwrap()
+        });
+        static GO: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^(func|type|var|const)\b").unwrap());
+        static SOLIDITY: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^\s{0,4}(abstract\s+)?(contract|library|interface|function|struct|event|modifier|error|enum|constructor)\b").unwrap()
+        });
+
+        match self {
+            Language::Rust => &RUST,
+            Language::Python => &PYTHON,
+            Language::JavaScript => &JAVASCRIPT,
+            Language::Go => &GO,
+            Language::Solidity => &SOLIDITY,
+        }
+    }
+
+    /// Whether `line` belongs to the definition below it (doc comment, attribute, decorator)
+    fn is_preamble(&self, line: &str) -> bool {
+        let line = line.trim_start();
+        match self {
+            Language::Rust => line.starts_with("//") || line.starts_with("#["),
+            Language::Python => line.starts_with('#') || line.starts_with('@'),
+            Language::JavaScript | Language::Go | Language::Solidity => {
+                line.starts_with("//")
+                    || line.starts_with("/*")
+                    || line.starts_with('*')
+                    || line.starts_with('@')
+            }
+        }
+    }
+}
+
+/// Keeps top-level definitions of a language together, with the comments and attributes
+/// preceding them. Definitions too large for a chunk are split on blank lines.
+#[derive(Debug, Clone)]
+pub struct CodeChunker {
+    pub language: Language,
+    pub size: ChunkSize,
+}
+
+impl CodeChunker {
+    pub fn new(language: Language, size: ChunkSize) -> Self {
+        Self { language, size }
+    }
+}
+
+impl Chunker for CodeChunker {
+    fn chunk(&self, text: &str) -> Vec<Chunk> {
+        let lines: Vec<(usize, &str)> = lines(text).collect();
+        let mut cuts = Vec::new();
+
+        for (i, (_, line)) in lines.iter().enumerate() {
+            if !self.language.definition().is_match(line) {
+                continue;
+            }
+            let mut start = i;
+            while start > 0 && self.language.is_preamble(lines[start - 1].1) {
+                start -= 1;
+            }
+            cuts.push(lines[start].0);
+        }
+        cuts.dedup();
+
+        pack(text, ranges_from_cuts(0..text.len(), cuts), self.size)
+    }
+}
+
+/// Lines of `text` with their byte offset, line breaks included
+fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+    })
+}
+
+/// Splits `range` into consecutive ranges starting at each cut inside it
+fn ranges_from_cuts(range: Range<usize>, cuts: Vec<usize>) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = range.start;
+    for cut in cuts {
+        if cut > start && cut < range.end {
+            ranges.push(start..cut);
+            start = cut;
+        }
+    }
+    if start < range.end {
+        ranges.push(start..range.end);
+    }
+    ranges
+}
+
+/// Splits `range` after every match of `separator`
+fn split_matches(text: &str, range: Range<usize>, separator: &Regex) -> Vec<Range<usize>> {
+    let cuts = separator
+        .find_iter(&text[range.clone()])
+        .map(|found| range.start + found.end())
+        .collect();
+    ranges_from_cuts(range, cuts)
+}
+
+/// Splits `range` into pieces of at most `max_tokens`, on paragraphs, then sentences, then
+/// words and as a last resort on characters
+fn split_oversized(text: &str, range: Range<usize>, max_tokens: usize) -> Vec<Range<usize>> {
+    if estimate_tokens(&text[range.clone()]) <= max_tokens {
+        return vec![range];
+    }
+
+    for separator in [&*PARAGRAPH_BREAK, &*SENTENCE_END, &*WHITESPACE] {
+        let parts = split_matches(text, range.clone(), separator);
+        if parts.len() > 1 {
+            return parts
+                .into_iter()
+                .flat_map(|part| split_oversized(text, part, max_tokens))
+                .collect();
+        }
+    }
+
+    let max_chars = max_tokens.max(1) * 4;
+    let cuts = text[range.clone()]
+        .char_indices()
+        .map(|(i, _)| range.start + i)
+        .step_by(max_chars)
+        .collect();
+    ranges_from_cuts(range, cuts)
+}
+
+/// Greedily packs consecutive segments into chunks of at most `size.max_tokens`, starting
+/// each chunk with the trailing segments of the previous one that fit in the overlap
+fn pack(text: &str, segments: Vec<Range<usize>>, size: ChunkSize) -> Vec<Chunk> {
+    let max_tokens = size.max_tokens.max(1);
+    let overlap_tokens = size.overlap_tokens.min(max_tokens / 2);
+
+    let units: Vec<(Range<usize>, usize)> = segments
+        .into_iter()
+        .flat_map(|segment| split_oversized(text, segment, max_tokens))
+        .map(|unit| {
+            let tokens = estimate_tokens(&text[unit.clone()]);
+            (unit, tokens)
+        })
+        .collect();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < units.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        while end < units.len() && (end == start || tokens + units[end].1 <= max_tokens) {
+            tokens += units[end].1;
+            end += 1;
+        }
+
+        let slice = &text[units[start].0.start..units[end - 1].0.end];
+        let trimmed = slice.trim();
+        if !trimmed.is_empty() {
+            chunks.push(Chunk {
+                text: trimmed.to_string(),
+                offset: units[start].0.start + (slice.len() - slice.trim_start().len()),
+            });
+        }
+
+        if end == units.len() {
+            break;
+        }
+
+        let mut next = end;
+        let mut overlap = 0;
+        while next > start + 1 && overlap + units[next - 1].1 <= overlap_tokens {
+            overlap += units[next - 1].1;
+            next -= 1;
+        }
+        start = next;
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: ChunkSize = ChunkSize {
+        max_tokens: 20,
+        overlap_tokens: 0,
+    };
+
+    /// Every non-whitespace character of `text` must be part of a chunk
+    fn assert_covers(text: &str, chunks: &[Chunk]) {
+        let mut covered = vec![false; text.len()];
+        for chunk in chunks {
+            assert_eq!(
+                &text[chunk.offset..chunk.offset + chunk.text.len()],
+                chunk.text
+            );
+            covered[chunk.offset..chunk.offset + chunk.text.len()].fill(true);
+        }
+        for (i, c) in text.char_indices() {
+            assert!(c.is_whitespace() || covered[i], "lost {:?} at {}", c, i);
+        }
+    }
+
+    #[test]
+    fn sentences_without_terminator_are_kept() {
+        let text = "Gaia nodes run models. They serve an OpenAI-compatible API. And this trailing text has no terminator at all";
+        let chunks = SentenceChunker { size: SMALL }.chunk(text);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|chunk| estimate_tokens(&chunk.text) <= 20));
+        assert!(chunks
+            .last()
+            .unwrap()
+            .text
+            .ends_with("no terminator at all"));
+        assert_covers(text, &chunks);
+    }
+
+    #[test]
+    fn long_text_without_separators_is_split_on_characters() {
+        let text = "x".repeat(1000);
+        let chunks = ParagraphChunker { size: SMALL }.chunk(&text);
+
+        assert_eq!(chunks.len(), 13);
+        assert_covers(&text, &chunks);
+    }
+
+    #[test]
+    fn overlap_repeats_the_end_of_the_previous_chunk() {
+        let text = "One two three. Four five six. Seven eight nine. Ten eleven twelve.";
+        let size = ChunkSize {
+            max_tokens: 10,
+            overlap_tokens: 5,
+        };
+        let chunks = SentenceChunker { size }.chunk(text);
+
+        assert_eq!(chunks[0].text, "One two three. Four five six.");
+        assert_eq!(chunks[1].text, "Four five six. Seven eight nine.");
+        assert_covers(text, &chunks);
+    }
+
+    #[test]
+    fn markdown_sections_start_at_headings() {
+        let text = "# Setup\nInstall gaianet first.\n\n```sh\n# not a heading\ngaianet init\n```\n\n## Run\nStart the node with gaianet start.";
+        let chunks = MarkdownChunker { size: SMALL }.chunk(text);
+
+        assert!(chunks[0].text.starts_with("# Setup"));
+        assert!(chunks.iter().any(|chunk| chunk.text.starts_with("## Run")));
+        assert!(!chunks
+            .iter()
+            .any(|chunk| chunk.text.starts_with("# not a heading")));
+        assert_covers(text, &chunks);
+    }
+
+    #[test]
+    fn code_is_split_on_definitions_with_their_comments() {
+        let text = "use std::fmt;\n\n/// Adds\n#[inline]\npub fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n\nstruct Point {\n    x: u32,\n}\n";
+        let chunks = CodeChunker::new(Language::Rust, SMALL).chunk(text);
+
+        assert_eq!(chunks[0].text, "use std::fmt;");
+        assert!(chunks[1]
+            .text
+            .starts_with("/// Adds\n#[inline]\npub fn add"));
+        assert!(chunks
+            .iter()
+            .any(|chunk| chunk.text.starts_with("struct Point")));
+        assert_covers(text, &chunks);
+    }
+}
@@ -0,0 +1,179 @@
+use super::chunker::ChunkSize;
+use crate::types::GaiaNodeConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Similarity measure of a vector collection, named like Qdrant's distances
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Distance {
+    Cosine,
+    Euclid,
+    Dot,
+    Manhattan,
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Distance::Cosine => write!(f, "Cosine"),
+            Distance::Euclid => write!(f, "Euclid"),
+            Distance::Dot => write!(f, "Dot"),
+            Distance::Manhattan => write!(f, "Manhattan"),
+        }
+    }
+}
+
+impl FromStr for Distance {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cosine" => Ok(Distance::Cosine),
+            "euclid" | "euclidean" => Ok(Distance::Euclid),
+            "dot" => Ok(Distance::Dot),
+            "manhattan" => Ok(Distance::Manhattan),
+            other => Err(format!("unknown distance: {}", other)),
+        }
+    }
+}
+
+/// Endpoints and models used by `DynamicRAG`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagConfig {
+    /// Base URL of the OpenAI-compatible API serving embeddings
+    pub embedding_url: String,
+    pub embedding_model: String,
+    /// Chunks sent per embedding request
+    pub embedding_batch_size: usize,
+    /// Embedding requests in flight at once
+    pub embedding_concurrency: usize,
+    /// Retries of a request failing with a transient error
+    pub max_retries: u32,
+    /// Base URL of the OpenAI-compatible API serving chat completions
+    pub chat_url: String,
+    pub chat_model: String,
+    /// gRPC URL of the Qdrant instance storing the vectors
+    pub qdrant_url: String,
+    /// REST URL of the same Qdrant instance, used to transfer snapshots
+    pub qdrant_rest_url: String,
+    /// Dimension of the embeddings, detected from the first embedding response when `None`
+    pub vector_size: Option<u64>,
+    pub distance: Distance,
+    /// Number of chunks retrieved for a question
+    pub top_k: u64,
+    /// Approximate number of tokens of retrieved context sent to the chat model
+    pub context_tokens: usize,
+    pub chunk_size: ChunkSize,
+}
+
+impl Default for RagConfig {
+    fn default() -> Self {
+        Self {
+            embedding_url: "http://localhost:8080/v1".to_string(),
+            embedding_model: "nomic-embed".to_string(),
+            embedding_batch_size: 16,
+            embedding_concurrency: 4,
+            max_retries: 5,
+            chat_url: "http://localhost:8080/v1".to_string(),
+            chat_model: "llama".to_string(),
+            qdrant_url: "http://localhost:6334".to_string(),
+            qdrant_rest_url: "http://localhost:6333".to_string(),
+            vector_size: None,
+            distance: Distance::Cosine,
+            top_k: 5,
+            context_tokens: 2048,
+            chunk_size: ChunkSize::default(),
+        }
+    }
+}
+
+impl RagConfig {
+    /// Defaults overridden by `RAG_EMBEDDING_URL`, `RAG_EMBEDDING_MODEL`,
+    /// `RAG_EMBEDDING_BATCH_SIZE`, `RAG_EMBEDDING_CONCURRENCY`, `RAG_MAX_RETRIES`,
+    /// `RAG_CHAT_URL`, `RAG_CHAT_MODEL`, `QDRANT_URL`, `QDRANT_REST_URL`, `RAG_VECTOR_SIZE`,
+    /// `RAG_DISTANCE`, `RAG_TOP_K`, `RAG_CONTEXT_TOKENS`, `RAG_CHUNK_TOKENS` and
+    /// `RAG_CHUNK_OVERLAP_TOKENS`
+    pub fn from_env() -> Self {
+        Self::default().with_env_overrides()
+    }
+
+    /// Points at the API of a node started with `node`, using the chat and embedding models
+    /// from its data dir when it is initialized. Environment variables still take precedence.
+    pub fn from_node_config(node: &GaiaNodeConfig) -> Self {
+        let mut config = Self {
+            embedding_url: node.api_url(),
+            chat_url: node.api_url(),
+            ..Self::default()
+        };
+
+        let node_config = std::fs::read_to_string(node.data_path().join("config.json"))
+            .ok()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok());
+        if let Some(node_config) = node_config {
+            if let Some(model) = node_config["embedding_name"].as_str() {
+                config.embedding_model = model.to_string();
+            }
+            if let Some(model) = node_config["chat_name"].as_str() {
+                config.chat_model = model.to_string();
+            }
+        }
+
+        config.with_env_overrides()
+    }
+
+    fn with_env_overrides(mut self) -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        if let Some(url) = var("RAG_EMBEDDING_URL") {
+            self.embedding_url = url;
+        }
+        if let Some(model) = var("RAG_EMBEDDING_MODEL") {
+            self.embedding_model = model;
+        }
+        if let Some(size) = var("RAG_EMBEDDING_BATCH_SIZE").and_then(|size| size.parse().ok()) {
+            self.embedding_batch_size = size;
+        }
+        if let Some(concurrency) =
+            var("RAG_EMBEDDING_CONCURRENCY").and_then(|concurrency| concurrency.parse().ok())
+        {
+            self.embedding_concurrency = concurrency;
+        }
+        if let Some(retries) = var("RAG_MAX_RETRIES").and_then(|retries| retries.parse().ok()) {
+            self.max_retries = retries;
+        }
+        if let Some(url) = var("RAG_CHAT_URL") {
+            self.chat_url = url;
+        }
+        if let Some(model) = var("RAG_CHAT_MODEL") {
+            self.chat_model = model;
+        }
+        if let Some(url) = var("QDRANT_URL") {
+            self.qdrant_url = url;
+        }
+        if let Some(url) = var("QDRANT_REST_URL") {
+            self.qdrant_rest_url = url;
+        }
+        if let Some(size) = var("RAG_VECTOR_SIZE").and_then(|size| size.parse().ok()) {
+            self.vector_size = Some(size);
+        }
+        if let Some(distance) = var("RAG_DISTANCE").and_then(|distance| distance.parse().ok()) {
+            self.distance = distance;
+        }
+        if let Some(top_k) = var("RAG_TOP_K").and_then(|top_k| top_k.parse().ok()) {
+            self.top_k = top_k;
+        }
+        if let Some(tokens) = var("RAG_CONTEXT_TOKENS").and_then(|tokens| tokens.parse().ok()) {
+            self.context_tokens = tokens;
+        }
+        if let Some(tokens) = var("RAG_CHUNK_TOKENS").and_then(|tokens| tokens.parse().ok()) {
+            self.chunk_size.max_tokens = tokens;
+        }
+        if let Some(tokens) = var("RAG_CHUNK_OVERLAP_TOKENS").and_then(|tokens| tokens.parse().ok())
+        {
+            self.chunk_size.overlap_tokens = tokens;
+        }
+
+        self
+    }
+}
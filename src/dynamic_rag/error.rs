@@ -0,0 +1,48 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, RagError>;
+
+#[derive(Debug)]
+pub enum RagError {
+    /// The HTTP request could not be sent or its body not read
+    Http(String),
+    /// An endpoint answered with a non-success status
+    Api {
+        status: u16,
+        body: String,
+    },
+    /// A response didn't have the expected shape
+    InvalidResponse(String),
+    /// The vector store rejected an operation
+    VectorStore(String),
+    /// A document could not be read or parsed
+    Ingest(String),
+    Internal(String),
+}
+
+impl fmt::Display for RagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RagError::Http(msg) => write!(f, "HTTP error: {}", msg),
+            RagError::Api { status, body } => write!(f, "API error ({}): {}", status, body),
+            RagError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
+            RagError::VectorStore(msg) => write!(f, "Vector store error: {}", msg),
+            RagError::Ingest(msg) => write!(f, "Ingestion error: {}", msg),
+            RagError::Internal(msg) => write!(f, "Internal error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RagError {}
+
+impl From<reqwest::Error> for RagError {
+    fn from(e: reqwest::Error) -> Self {
+        RagError::Http(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for RagError {
+    fn from(e: std::io::Error) -> Self {
+        RagError::Ingest(e.to_string())
+    }
+}
@@ -0,0 +1,329 @@
+use super::chunker::{Chunker, CodeChunker, Language, MarkdownChunker};
+use super::error::{RagError, Result};
+use super::rag::DynamicRAG;
+use blueprint_sdk::logging::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+static SPACES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[ \t\u{a0}]+").unwrap());
+static PDF_HYPHENATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\w)-\n(\w)").unwrap());
+static MARKDOWN_FENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}(```|~~~)").unwrap());
+static MARKDOWN_COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+static MARKDOWN_IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!\[([^\]]*)\]\([^)]*\)").unwrap());
+static MARKDOWN_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]]+)\]\([^)]*\)").unwrap());
+static HTML_HIDDEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<!--.*?-->|<script\b.*?</script>|<style\b.*?</style>|<head\b.*?</head>")
+        .unwrap()
+});
+static HTML_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)</?(p|div|br|li|ul|ol|tr|table|section|article|h[1-6]|pre|blockquote)\b[^>]*>")
+        .unwrap()
+});
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// File formats the ingestion understands, picked by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocumentFormat {
+    Pdf,
+    Markdown,
+    Html,
+    Text,
+    Code(Language),
+}
+
+impl DocumentFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "pdf" => Some(DocumentFormat::Pdf),
+            "md" | "markdown" => Some(DocumentFormat::Markdown),
+            "html" | "htm" => Some(DocumentFormat::Html),
+            "txt" | "text" => Some(DocumentFormat::Text),
+            extension => Language::from_extension(extension).map(DocumentFormat::Code),
+        }
+    }
+}
+
+/// Normalized text of a document, or of a single page for PDFs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSection {
+    pub path: PathBuf,
+    pub format: DocumentFormat,
+    /// 1-based page number, only known for PDFs
+    pub page: Option<u32>,
+    pub text: String,
+}
+
+/// Where a chunk was taken from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkSource {
+    pub path: PathBuf,
+    pub page: Option<u32>,
+    /// Byte offset of the chunk in the normalized text of its section
+    pub offset: usize,
+}
+
+/// A chunk ready to be embedded together with its source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceChunk {
+    pub text: String,
+    pub source: ChunkSource,
+}
+
+/// Loads a file, or every supported file below a directory, into normalized sections.
+/// Files with unsupported extensions inside a directory are skipped, so are files that fail
+/// to load. Reading is blocking, async callers should go through `spawn_blocking`.
+pub fn load_path(path: &Path) -> Result<Vec<DocumentSection>> {
+    if path.is_dir() {
+        let mut sections = Vec::new();
+        for file in collect_files(path)? {
+            match load_file(&file) {
+                Ok(file_sections) => sections.extend(file_sections),
+                Err(e) => warn!("Skipping {}: {}", file.display(), e),
+            }
+        }
+        Ok(sections)
+    } else {
+        load_file(path)
+    }
+}
+
+/// Loads a single file into normalized sections
+pub fn load_file(path: &Path) -> Result<Vec<DocumentSection>> {
+    let format = DocumentFormat::from_path(path).ok_or_else(|| {
+        RagError::Ingest(format!("unsupported document type: {}", path.display()))
+    })?;
+
+    let section = |page, text: String| DocumentSection {
+        path: path.to_path_buf(),
+        format,
+        page,
+        text,
+    };
+
+    let sections = match format {
+        DocumentFormat::Pdf => {
+            let bytes = std::fs::read(path)?;
+            let pages = pdf_extract::extract_text_from_mem_by_pages(&bytes).map_err(|e| {
+                RagError::Ingest(format!("failed to extract {}: {}", path.display(), e))
+            })?;
+            pages
+                .into_iter()
+                .enumerate()
+                .map(|(i, page)| section(Some(i as u32 + 1), normalize_pdf(&page)))
+                .collect()
+        }
+        DocumentFormat::Markdown => {
+            vec![section(
+                None,
+                markdown_to_text(&std::fs::read_to_string(path)?),
+            )]
+        }
+        DocumentFormat::Html => {
+            vec![section(None, html_to_text(&std::fs::read_to_string(path)?))]
+        }
+        DocumentFormat::Text => vec![section(None, normalize(&std::fs::read_to_string(path)?))],
+        // Indentation matters in code, only line endings are unified
+        DocumentFormat::Code(_) => vec![section(
+            None,
+            std::fs::read_to_string(path)?.replace("\r\n", "\n"),
+        )],
+    };
+
+    Ok(sections
+        .into_iter()
+        .filter(|section: &DocumentSection| !section.text.is_empty())
+        .collect())
+}
+
+/// Supported files below `dir`, in a stable order
+fn collect_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if DocumentFormat::from_path(&path).is_some() {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Splits the sections into chunks, keeping track of where each chunk starts in its
+/// section. Markdown and code are split along their structure, everything else with the
+/// chunker of `rag`.
+pub fn chunk_sections(rag: &DynamicRAG, sections: &[DocumentSection]) -> Vec<SourceChunk> {
+    let size = rag.config().chunk_size;
+    let markdown = MarkdownChunker { size };
+
+    sections
+        .iter()
+        .flat_map(|section| {
+            let chunks = match section.format {
+                DocumentFormat::Markdown => markdown.chunk(&section.text),
+                DocumentFormat::Code(language) => {
+                    CodeChunker::new(language, size).chunk(&section.text)
+                }
+                _ => rag.chunker().chunk(&section.text),
+            };
+
+            chunks.into_iter().map(|chunk| SourceChunk {
+                text: chunk.text,
+                source: ChunkSource {
+                    path: section.path.clone(),
+                    page: section.page,
+                    offset: chunk.offset,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Unifies line endings and whitespace: drops control characters, collapses runs of spaces
+/// and keeps at most one blank line between paragraphs
+pub fn normalize(text: &str) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut normalized = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in text.lines() {
+        let line: String = line
+            .chars()
+            .filter(|c| !c.is_control() || *c == '\t')
+            .collect();
+        let line = SPACES.replace_all(line.trim(), " ");
+
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !normalized.is_empty() {
+            normalized.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        normalized.push_str(&line);
+        blank_lines = 0;
+    }
+
+    normalized
+}
+
+/// Normalizes extracted PDF text and rejoins words hyphenated across lines
+fn normalize_pdf(text: &str) -> String {
+    let text = normalize(text);
+    PDF_HYPHENATION.replace_all(&text, "$1$2").into_owned()
+}
+
+/// Drops link and image targets but keeps headings and code, which the chunker relies on.
+/// Fenced code blocks are kept verbatim, their indentation matters.
+fn markdown_to_text(markdown: &str) -> String {
+    let markdown = markdown.replace("\r\n", "\n");
+    let mut parts = Vec::new();
+    let mut prose = String::new();
+    // marker of the fenced block being read and the lines read so far
+    let mut fenced: Option<(String, String)> = None;
+
+    for line in markdown.lines() {
+        if let Some((marker, block)) = &mut fenced {
+            block.push('\n');
+            block.push_str(line);
+            if line.trim_start().starts_with(marker.as_str()) {
+                parts.push(std::mem::take(block));
+                fenced = None;
+            }
+        } else if let Some(fence) = MARKDOWN_FENCE.captures(line) {
+            parts.push(markdown_prose_to_text(&std::mem::take(&mut prose)));
+            fenced = Some((fence[1].to_string(), line.to_string()));
+        } else {
+            prose.push_str(line);
+            prose.push('\n');
+        }
+    }
+    // an unclosed fence runs to the end of the document
+    if let Some((_, block)) = fenced {
+        parts.push(block);
+    }
+    parts.push(markdown_prose_to_text(&prose));
+
+    parts.retain(|part| !part.trim().is_empty());
+    parts.join("\n\n")
+}
+
+/// Markdown outside of fenced code blocks as normalized text
+fn markdown_prose_to_text(markdown: &str) -> String {
+    let text = MARKDOWN_COMMENT.replace_all(markdown, "");
+    let text = MARKDOWN_IMAGE.replace_all(&text, "$1");
+    let text = MARKDOWN_LINK.replace_all(&text, "$1");
+    normalize(&text)
+}
+
+/// Strips tags, scripts and styles, turning block elements into line breaks
+fn html_to_text(html: &str) -> String {
+    let text = HTML_HIDDEN.replace_all(html, "");
+    let text = HTML_BLOCK.replace_all(&text, "\n");
+    let text = HTML_TAG.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    normalize(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_collapses_whitespace_and_blank_lines() {
+        let text = "  Gaia\t\tnodes \r\n\r\n\r\n\r\nserve   models\u{7}\n";
+        assert_eq!(normalize(text), "Gaia nodes\n\nserve models");
+    }
+
+    #[test]
+    fn html_keeps_text_and_block_structure() {
+        let html = "<html><head><title>x</title></head><body><h1>Nodes</h1>\
+                    <script>alert(1)</script><p>Run &amp; serve <b>models</b></p></body></html>";
+        assert_eq!(html_to_text(html), "Nodes\n\nRun & serve models");
+    }
+
+    #[test]
+    fn markdown_drops_link_targets() {
+        let markdown = "# Setup\n\nSee [the docs](https://docs.gaianet.ai) ![logo](logo.png)";
+        assert_eq!(markdown_to_text(markdown), "# Setup\n\nSee the docs logo");
+    }
+
+    #[test]
+    fn markdown_keeps_fenced_code_verbatim() {
+        let markdown =
+            "Run   it:\n\n```rust\nfn main() {\n    let  x = [a](b);\n}\n```\n\n\n\nDone";
+        assert_eq!(
+            markdown_to_text(markdown),
+            "Run it:\n\n```rust\nfn main() {\n    let  x = [a](b);\n}\n```\n\nDone"
+        );
+    }
+
+    #[test]
+    fn unreadable_files_dont_abort_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("broken.pdf"), "not a pdf").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "Gaia nodes").unwrap();
+
+        let sections = load_path(dir.path()).unwrap();
+
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].text, "Gaia nodes");
+    }
+}
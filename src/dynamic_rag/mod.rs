@@ -1 +1,18 @@
+pub mod chunker;
+pub mod config;
+pub mod error;
+pub mod ingest;
 pub mod rag;
+pub mod snapshot;
+pub mod store;
+
+pub use chunker::{
+    Chunk, ChunkSize, Chunker, CodeChunker, Language, MarkdownChunker, ParagraphChunker,
+    SentenceChunker,
+};
+pub use config::{Distance, RagConfig};
+pub use error::{RagError, Result};
+pub use ingest::{ChunkSource, DocumentFormat, DocumentSection, SourceChunk};
+pub use rag::{DynamicRAG, EmbeddingData, EmbeddingProgress, RagAnswer, RetrievedChunk};
+pub use snapshot::SnapshotFile;
+pub use store::{QdrantStore, VectorPoint, VectorStore};
@@ -1,310 +1,529 @@
-pub const MAX_CHUNK_SIZE: usize = 2000; // Maximum characters per chunk
-pub const BATCH_SIZE: usize = 3; // Number of chunks to process at once
-
-use anyhow::{Context, Result};
-use regex::Regex;
-use reqwest::Client;
+use super::chunker::{estimate_tokens, Chunker, ParagraphChunker};
+use super::config::RagConfig;
+use super::error::{RagError, Result};
+use super::ingest::{self, ChunkSource};
+use super::store::{QdrantStore, VectorPoint, VectorStore};
+use crate::metrics::METRICS;
+use futures::{StreamExt, TryStreamExt};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Delay before the first retry of a failed request, doubled on every following attempt
+const RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Upper bound for the retry delay
+const RETRY_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A chunk of text together with its embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingData {
+    pub text: String,
+    pub embedding: Vec<f32>,
+    /// Document the text was taken from, when ingested from files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ChunkSource>,
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Block {
-    block_type: BlockType,
-    content: String,
+/// Progress of `DynamicRAG::embed_all`, reported after every batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddingProgress {
+    pub embedded: usize,
+    pub total: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum BlockType {
-    Code,
-    Text,
+/// A stored chunk returned by a similarity search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievedChunk {
+    /// Id of the point in the collection
+    pub id: String,
+    pub score: f32,
+    pub text: String,
+    pub source: Option<ChunkSource>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct EmbeddingData {
-    text: String,
-    embedding: Vec<f32>,
+/// Answer of the chat model together with the chunks it was given as context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagAnswer {
+    pub answer: String,
+    pub sources: Vec<RetrievedChunk>,
 }
 
+/// Retrieval augmented generation over a Gaia node: text is split into chunks, embedded by
+/// the node, stored in Qdrant and used as context when asking the node's chat model
 pub struct DynamicRAG {
     client: Client,
+    config: RagConfig,
+    store: Arc<dyn VectorStore>,
+    chunker: Arc<dyn Chunker>,
+    /// Dimension of the embeddings, taken from the config or the first embedding response
+    vector_size: Mutex<Option<u64>>,
 }
 
 impl DynamicRAG {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self> {
+        Self::with_config(RagConfig::default())
+    }
+
+    /// Uses the Qdrant instance at `config.qdrant_url` as vector store
+    pub fn with_config(config: RagConfig) -> Result<Self> {
+        let store = QdrantStore::from_config(&config)?;
+        Ok(Self::with_store(config, Arc::new(store)))
+    }
+
+    pub fn with_store(config: RagConfig, store: Arc<dyn VectorStore>) -> Self {
         Self {
             client: Client::new(),
+            vector_size: Mutex::new(config.vector_size),
+            chunker: Arc::new(ParagraphChunker {
+                size: config.chunk_size,
+            }),
+            config,
+            store,
         }
     }
 
-    fn is_code_block(&self, text: &str) -> bool {
-        let code_indicators = vec![
-            Regex::new(r"^import\s+").unwrap(),
-            Regex::new(r"^const\s+").unwrap(),
-            Regex::new(r"^let\s+").unwrap(),
-            Regex::new(r"^function\s+").unwrap(),
-            Regex::new(r"^class\s+").unwrap(),
-            Regex::new(r"=>").unwrap(),
-            Regex::new(r"{\s*$").unwrap(),
-            Regex::new(r"^\s*}").unwrap(),
-            Regex::new(r"^\s*return\s+").unwrap(),
-            Regex::new(r"^\s*if\s*\(").unwrap(),
-            Regex::new(r"^\s*for\s*\(").unwrap(),
-            Regex::new(r"^\s*while\s*\(").unwrap(),
-        ];
+    /// Replaces the default paragraph chunker
+    pub fn with_chunker(mut self, chunker: Arc<dyn Chunker>) -> Self {
+        self.chunker = chunker;
+        self
+    }
 
-        code_indicators.iter().any(|pattern| pattern.is_match(text))
+    pub fn chunker(&self) -> &dyn Chunker {
+        self.chunker.as_ref()
     }
 
-    fn find_matching_brace(&self, text: &str, start_index: usize) -> usize {
-        let chars: Vec<char> = text.chars().collect();
-        let mut count = 1;
+    pub fn config(&self) -> &RagConfig {
+        &self.config
+    }
 
-        for i in (start_index + 1)..chars.len() {
-            if chars[i] == '{' {
-                count += 1;
-            }
-            if chars[i] == '}' {
-                count -= 1;
+    /// Dimension of the embeddings if configured or already seen
+    pub fn vector_size(&self) -> Option<u64> {
+        *self.vector_size.lock().unwrap()
+    }
+
+    /// Records the dimension of the first embedding and rejects embeddings of another size
+    fn check_dimension(&self, embedding: &[f32]) -> Result<()> {
+        let mut vector_size = self.vector_size.lock().unwrap();
+        match *vector_size {
+            Some(size) if size != embedding.len() as u64 => {
+                Err(RagError::InvalidResponse(format!(
+                    "embedding has {} dimensions, expected {}",
+                    embedding.len(),
+                    size
+                )))
             }
-            if count == 0 {
-                return i;
+            Some(_) => Ok(()),
+            None => {
+                *vector_size = Some(embedding.len() as u64);
+                Ok(())
             }
         }
-
-        text.len()
     }
 
+    /// Splits `text` into chunks with the configured chunker
     pub fn create_chunks(&self, text: &str) -> Vec<String> {
-        let mut blocks = Vec::new();
-        let mut current_block = String::new();
-        let lines: Vec<&str> = text.lines().collect();
-
-        let mut i = 0;
-        while i < lines.len() {
-            let line = lines[i];
-
-            if self.is_code_block(line) {
-                if !current_block.is_empty() && !self.is_code_block(&current_block) {
-                    blocks.push(Block {
-                        block_type: BlockType::Text,
-                        content: current_block.trim().to_string(),
-                    });
-                    current_block.clear();
-                }
-
-                current_block.push_str(if current_block.is_empty() { "" } else { "\n" });
-                current_block.push_str(line);
-
-                if line.contains('{') {
-                    let mut brace_count = 1;
-                    while i + 1 < lines.len() && brace_count > 0 {
-                        i += 1;
-                        current_block.push_str("\n");
-                        current_block.push_str(lines[i]);
-                        brace_count += lines[i].matches('{').count();
-                        brace_count -= lines[i].matches('}').count();
-                    }
-                }
-            } else {
-                if !current_block.is_empty() && self.is_code_block(&current_block) {
-                    blocks.push(Block {
-                        block_type: BlockType::Code,
-                        content: current_block.trim().to_string(),
-                    });
-                    current_block.clear();
-                }
-                current_block.push_str(if current_block.is_empty() { "" } else { "\n" });
-                current_block.push_str(line);
-            }
-            i += 1;
-        }
+        self.chunker
+            .chunk(text)
+            .into_iter()
+            .map(|chunk| chunk.text)
+            .collect()
+    }
 
-        if !current_block.is_empty() {
-            blocks.push(Block {
-                block_type: if self.is_code_block(&current_block) {
-                    BlockType::Code
-                } else {
-                    BlockType::Text
-                },
-                content: current_block.trim().to_string(),
-            });
+    /// Embeds one batch of chunks in a single request. The response must hold exactly one
+    /// embedding of the expected dimension per chunk.
+    pub async fn embed_batch(&self, batch: &[String]) -> Result<Vec<EmbeddingData>> {
+        let url = format!(
+            "{}/embeddings",
+            self.config.embedding_url.trim_end_matches('/')
+        );
+        let body = serde_json::json!({
+            "model": self.config.embedding_model,
+            "input": batch
+        });
+
+        let request = async {
+            let response = self
+                .send_with_retry(|| self.client.post(&url).json(&body))
+                .await?;
+            check_response(response).await
+        };
+        let response = METRICS.rag_request("embedding", request).await?;
+
+        let data = response["data"]
+            .as_array()
+            .ok_or_else(|| RagError::InvalidResponse("embedding response has no data".into()))?;
+        if data.len() != batch.len() {
+            return Err(RagError::InvalidResponse(format!(
+                "got {} embeddings for {} inputs",
+                data.len(),
+                batch.len()
+            )));
         }
 
-        let mut chunks = Vec::new();
-        for block in blocks {
-            match block.block_type {
-                BlockType::Code => {
-                    if block.content.len() <= MAX_CHUNK_SIZE {
-                        chunks.push(block.content);
-                    } else {
-                        let lines: Vec<&str> = block.content.lines().collect();
-                        let mut current_chunk = String::new();
-
-                        for line in lines {
-                            if current_chunk.len() + line.len() > MAX_CHUNK_SIZE {
-                                if !current_chunk.is_empty() {
-                                    chunks.push(current_chunk.trim().to_string());
-                                }
-                                current_chunk = line.to_string();
-                            } else {
-                                if !current_chunk.is_empty() {
-                                    current_chunk.push_str("\n");
-                                }
-                                current_chunk.push_str(line);
-                            }
-                        }
-                        if !current_chunk.is_empty() {
-                            chunks.push(current_chunk.trim().to_string());
-                        }
-                    }
-                }
-                BlockType::Text => {
-                    if block.content.len() <= MAX_CHUNK_SIZE {
-                        chunks.push(block.content);
-                    } else {
-                        let re = Regex::new(r"[^.!?]+[.!?]+").unwrap();
-                        let sentences: Vec<String> = re
-                            .find_iter(&block.content)
-                            .map(|m| m.as_str().to_string())
-                            .collect();
-
-                        let mut current_chunk = String::new();
-                        for sentence in sentences {
-                            if current_chunk.len() + sentence.len() > MAX_CHUNK_SIZE {
-                                if !current_chunk.is_empty() {
-                                    chunks.push(current_chunk.trim().to_string());
-                                }
-                                current_chunk = sentence;
-                            } else {
-                                current_chunk.push_str(" ");
-                                current_chunk.push_str(&sentence);
-                            }
-                        }
-                        if !current_chunk.is_empty() {
-                            chunks.push(current_chunk.trim().to_string());
-                        }
-                    }
-                }
+        // Embeddings carry the index of their input, don't rely on the order of the response
+        let mut embeddings: Vec<Option<Vec<f32>>> = vec![None; batch.len()];
+        for (position, item) in data.iter().enumerate() {
+            let index = item["index"]
+                .as_u64()
+                .map_or(position, |index| index as usize);
+            let embedding: Vec<f32> = serde_json::from_value(item["embedding"].clone())
+                .map_err(|e| RagError::InvalidResponse(format!("invalid embedding: {}", e)))?;
+            self.check_dimension(&embedding)?;
+
+            let slot = embeddings.get_mut(index).ok_or_else(|| {
+                RagError::InvalidResponse(format!("embedding index {} out of range", index))
+            })?;
+            if slot.replace(embedding).is_some() {
+                return Err(RagError::InvalidResponse(format!(
+                    "duplicate embedding for index {}",
+                    index
+                )));
             }
         }
 
-        chunks
-            .into_iter()
-            .filter(|chunk| !chunk.is_empty())
+        batch
+            .iter()
+            .zip(embeddings)
+            .map(|(text, embedding)| {
+                Ok(EmbeddingData {
+                    text: text.clone(),
+                    embedding: embedding.ok_or_else(|| {
+                        RagError::InvalidResponse(format!("no embedding for {:?}", text))
+                    })?,
+                    source: None,
+                })
+            })
             .collect()
     }
 
-    pub async fn process_batch(
+    /// Embeds all chunks in batches of `embedding_batch_size`, keeping up to
+    /// `embedding_concurrency` requests in flight. Embeddings are returned in the order of
+    /// `chunks` and `on_progress` is called after every batch.
+    pub async fn embed_all(
         &self,
         chunks: &[String],
-        start_idx: usize,
+        on_progress: impl Fn(EmbeddingProgress) + Sync,
     ) -> Result<Vec<EmbeddingData>> {
-        let batch_chunks: Vec<String> =
-            chunks[start_idx..std::cmp::min(start_idx + BATCH_SIZE, chunks.len())].to_vec();
-
-        let response = self
-            .client
-            .post("http://localhost:8080/v1/embeddings")
-            .json(&serde_json::json!({
-                "model": "nomic-embed",
-                "input": batch_chunks
-            }))
-            .send()
-            .await
-            .context("Failed to get embeddings")?;
+        let total = chunks.len();
+        let embedded = AtomicUsize::new(0);
+        let (embedded, on_progress) = (&embedded, &on_progress);
+
+        let batches: Vec<Vec<EmbeddingData>> =
+            futures::stream::iter(chunks.chunks(self.config.embedding_batch_size.max(1)))
+                .map(|batch| async move {
+                    let embeddings = self.embed_batch(batch).await?;
+                    let done = embedded.fetch_add(batch.len(), Ordering::Relaxed) + batch.len();
+                    on_progress(EmbeddingProgress {
+                        embedded: done,
+                        total,
+                    });
+                    Ok::<_, RagError>(embeddings)
+                })
+                .buffered(self.config.embedding_concurrency.max(1))
+                .try_collect()
+                .await?;
+
+        Ok(batches.into_iter().flatten().collect())
+    }
+
+    /// Embeds all chunks without reporting progress
+    pub async fn embed_chunks(&self, chunks: &[String]) -> Result<Vec<EmbeddingData>> {
+        self.embed_all(chunks, |_| {}).await
+    }
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            anyhow::bail!("Embedding creation failed: {}", error_text);
+    /// Sends the request built by `request`, retrying with exponential backoff on connection
+    /// errors, timeouts, rate limiting (429) and server errors. `Retry-After` is honored.
+    async fn send_with_retry(&self, request: impl Fn() -> RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let exhausted = attempt >= self.config.max_retries;
+            let retry_after = match request().send().await {
+                Ok(response) if exhausted || !is_retryable(response.status()) => {
+                    return Ok(response)
+                }
+                Ok(response) => retry_after(&response),
+                Err(e) if exhausted || !(e.is_connect() || e.is_timeout()) => return Err(e.into()),
+                Err(_) => None,
+            };
+
+            let backoff = RETRY_INITIAL_BACKOFF
+                .saturating_mul(2u32.saturating_pow(attempt))
+                .min(RETRY_MAX_BACKOFF);
+            tokio::time::sleep(retry_after.unwrap_or(backoff)).await;
+            attempt += 1;
         }
+    }
 
-        let embeddings: serde_json::Value = response.json().await?;
+    /// Runs the ingestion pipeline for `text` (chunking, embedding, storing) into `collection`
+    /// and returns the number of chunks stored
+    pub async fn ingest(&self, collection: &str, text: &str) -> Result<usize> {
+        let chunks = self.create_chunks(text);
+        let embeddings = self.embed_chunks(&chunks).await?;
+        self.store(collection, &embeddings).await
+    }
 
-        let results: Vec<EmbeddingData> = batch_chunks
-            .iter()
-            .enumerate()
-            .filter_map(|(i, chunk)| {
-                embeddings["data"].get(i).and_then(|data| {
-                    Some(EmbeddingData {
-                        text: chunk.clone(),
-                        embedding: serde_json::from_value(data["embedding"].clone()).ok()?,
-                    })
-                })
-            })
-            .collect();
+    /// Runs the ingestion pipeline for a file or a directory of documents into `collection`
+    /// and returns the number of chunks stored
+    pub async fn ingest_path(&self, collection: &str, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref().to_path_buf();
+        let sections = tokio::task::spawn_blocking(move || ingest::load_path(&path))
+            .await
+            .map_err(|e| RagError::Internal(e.to_string()))??;
+
+        let chunks = ingest::chunk_sections(self, &sections);
+        let texts: Vec<String> = chunks.iter().map(|chunk| chunk.text.clone()).collect();
+        let mut embeddings = self.embed_chunks(&texts).await?;
+        if embeddings.len() != chunks.len() {
+            return Err(RagError::InvalidResponse(format!(
+                "got {} embeddings for {} chunks",
+                embeddings.len(),
+                chunks.len()
+            )));
+        }
+        for (data, chunk) in embeddings.iter_mut().zip(chunks) {
+            data.source = Some(chunk.source);
+        }
 
-        Ok(results)
+        self.store(collection, &embeddings).await
     }
 
-    pub async fn create_snapshot(&self, embeddings_data: &[EmbeddingData]) -> Result<String> {
-        let collection_name = format!(
-            "temp_{}",
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        );
+    /// Creates `collection` unless it exists. An existing collection must have the dimension
+    /// of our embeddings.
+    pub async fn ensure_collection(&self, collection: &str) -> Result<()> {
+        let vector_size = self.vector_size().ok_or_else(|| {
+            RagError::VectorStore("vector size unknown, nothing was embedded yet".into())
+        })?;
+        self.store
+            .ensure_collection(collection, vector_size, self.config.distance)
+            .await
+    }
 
-        self.client
-            .put(&format!(
-                "http://localhost:6333/collections/{}",
-                collection_name
-            ))
-            .json(&serde_json::json!({
-                "vectors": {
-                    "size": 768,
-                    "distance": "Cosine"
-                }
-            }))
-            .send()
-            .await?;
+    /// Upserts the embeddings into `collection`, creating it if missing, and returns the
+    /// number of points written. Points are keyed by the hash of their text, so storing a
+    /// chunk again updates it instead of adding a duplicate.
+    pub async fn store(
+        &self,
+        collection: &str,
+        embeddings_data: &[EmbeddingData],
+    ) -> Result<usize> {
+        if embeddings_data.is_empty() {
+            return Ok(0);
+        }
+        self.ensure_collection(collection).await?;
 
-        let points: Vec<serde_json::Value> = embeddings_data
+        let points = embeddings_data
             .iter()
-            .enumerate()
-            .map(|(i, item)| {
-                serde_json::json!({
-                    "id": i,
-                    "vector": item.embedding,
-                    "payload": { "text": item.text }
-                })
+            .map(|item| VectorPoint {
+                id: point_id(&item.text),
+                vector: item.embedding.clone(),
+                text: item.text.clone(),
+                source: item.source.clone(),
             })
             .collect();
+        self.store.upsert(collection, points).await?;
 
-        self.client
-            .put(&format!(
-                "http://localhost:6333/collections/{}/points",
-                collection_name
-            ))
-            .json(&serde_json::json!({ "points": points }))
-            .send()
-            .await?;
+        Ok(embeddings_data.len())
+    }
 
-        Ok(collection_name)
+    /// Names of the collections in the vector store
+    pub async fn list_collections(&self) -> Result<Vec<String>> {
+        self.store.list_collections().await
+    }
+
+    /// Deletes `collection` and its points, returns whether it existed
+    pub async fn delete_collection(&self, collection: &str) -> Result<bool> {
+        self.store.delete_collection(collection).await
+    }
+
+    /// Embeds a single piece of text
+    pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_batch(&[text.to_string()])
+            .await?
+            .pop()
+            .map(|data| data.embedding)
+            .ok_or_else(|| RagError::InvalidResponse("no embedding returned".into()))
+    }
+
+    /// Returns the `limit` chunks of `collection` closest to `vector`, best first
+    pub async fn search(
+        &self,
+        collection: &str,
+        vector: Vec<f32>,
+        limit: u64,
+    ) -> Result<Vec<RetrievedChunk>> {
+        self.store.search(collection, vector, limit).await
     }
 
+    /// Answers `question` from the chunks of `collection` most similar to it
+    pub async fn answer(&self, question: &str, collection: &str) -> Result<RagAnswer> {
+        let vector = self.embed_text(question).await?;
+        let hits = self.search(collection, vector, self.config.top_k).await?;
+        let (context, sources) = build_context(hits, self.config.context_tokens);
+        let answer = self.query_llm(question, &context).await?;
+
+        Ok(RagAnswer { answer, sources })
+    }
+
+    /// Asks the node's chat model to answer `user_query` using `context`
     pub async fn query_llm(&self, user_query: &str, context: &str) -> Result<String> {
-        let response = self.client.post("http://localhost:8080/v1/chat/completions")
-            .json(&serde_json::json!({
-                "model": "llama",
-                "messages": [
-                    {
-                        "role": "system",
-                        "content": "You are a helpful assistant. Use the provided context to answer questions."
-                    },
-                    {
-                        "role": "user",
-                        "content": format!("Context: {}\n\nQuestion: {}", context, user_query)
-                    }
-                ]
-            }))
-            .send()
-            .await?;
-
-        let completion: serde_json::Value = response.json().await?;
-        Ok(completion["choices"][0]["message"]["content"]
+        let url = format!(
+            "{}/chat/completions",
+            self.config.chat_url.trim_end_matches('/')
+        );
+        let body = serde_json::json!({
+            "model": self.config.chat_model,
+            "messages": [
+                {
+                    "role": "system",
+                    "content": "You are a helpful assistant. Use the provided context to answer questions."
+                },
+                {
+                    "role": "user",
+                    "content": format!("Context: {}\n\nQuestion: {}", context, user_query)
+                }
+            ]
+        });
+
+        let request = async {
+            let response = self.client.post(&url).json(&body).send().await?;
+            check_response(response).await
+        };
+        let completion = METRICS.rag_request("chat", request).await?;
+        completion["choices"][0]["message"]["content"]
             .as_str()
-            .unwrap_or("")
-            .to_string())
+            .map(str::to_string)
+            .ok_or_else(|| RagError::InvalidResponse("completion has no message content".into()))
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay requested by a `Retry-After` header given in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Stable id of the point storing `text`: a UUID made of the first bytes of its SHA-256
+fn point_id(text: &str) -> String {
+    let hash = Sha256::digest(text.as_bytes());
+    let hex: String = hash[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Joins the best chunks into a numbered context until `token_budget` is reached and returns
+/// it with the chunks that made it in
+fn build_context(hits: Vec<RetrievedChunk>, token_budget: usize) -> (String, Vec<RetrievedChunk>) {
+    let mut context = String::new();
+    let mut used_tokens = 0;
+    let mut sources = Vec::new();
+
+    for hit in hits {
+        let entry = format!("[{}] {}\n\n", sources.len() + 1, hit.text);
+        let tokens = estimate_tokens(&entry);
+        if used_tokens + tokens > token_budget {
+            break;
+        }
+        used_tokens += tokens;
+        context.push_str(&entry);
+        sources.push(hit);
+    }
+
+    (context.trim_end().to_string(), sources)
+}
+
+/// Turns a non-success status into `RagError::Api`
+pub(crate) async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(RagError::Api {
+            status: status.as_u16(),
+            body,
+        });
+    }
+
+    Ok(response)
+}
+
+/// Turns a non-success status into `RagError::Api` and parses the JSON body otherwise
+async fn check_response(response: reqwest::Response) -> Result<serde_json::Value> {
+    check_status(response)
+        .await?
+        .json()
+        .await
+        .map_err(|e| RagError::InvalidResponse(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(id: &str, text: &str) -> RetrievedChunk {
+        RetrievedChunk {
+            id: id.to_string(),
+            score: 0.9,
+            text: text.to_string(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn context_stops_at_the_token_budget() {
+        let hits = vec![
+            hit("a", "first chunk"),
+            hit("b", &"x".repeat(100)),
+            hit("c", "third"),
+        ];
+        let (context, sources) = build_context(hits, 10);
+        assert_eq!(context, "[1] first chunk");
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].id, "a");
+    }
+
+    #[test]
+    fn point_ids_are_stable_uuids() {
+        let id = point_id("Gaia nodes run models.");
+        assert_eq!(id, point_id("Gaia nodes run models."));
+        assert_ne!(id, point_id("Gaia nodes serve an API."));
+        assert_eq!(id.len(), 36);
+        assert_eq!(id.matches('-').count(), 4);
+    }
+
+    #[test]
+    fn empty_text_has_no_chunks() {
+        let rag = DynamicRAG::new().unwrap();
+        assert!(rag.create_chunks("").is_empty());
+        assert!(rag.create_chunks("\n\n").is_empty());
+    }
+
+    #[test]
+    fn short_text_is_a_single_chunk() {
+        let rag = DynamicRAG::new().unwrap();
+        let chunks = rag.create_chunks("Gaia nodes run models.\nThey serve an API.");
+        assert_eq!(chunks, vec!["Gaia nodes run models.\nThey serve an API."]);
     }
 }
@@ -0,0 +1,207 @@
+use super::error::{RagError, Result};
+use super::rag::check_status;
+use super::store::QdrantStore;
+use futures::{Stream, StreamExt};
+use qdrant_client::qdrant::DeleteSnapshotRequestBuilder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
+
+/// A collection snapshot stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub collection: String,
+    pub path: PathBuf,
+    pub size: u64,
+    /// Hex encoded SHA-256 of the file
+    pub checksum: String,
+}
+
+impl QdrantStore {
+    /// Snapshots `collection`, downloads the snapshot into `dir` (e.g. a Gaia node's data dir)
+    /// and removes it from the server. The checksum is written next to the file as
+    /// `<file>.sha256`, a download that fails or doesn't match the server's checksum is
+    /// deleted.
+    pub async fn export_snapshot(&self, collection: &str, dir: &Path) -> Result<SnapshotFile> {
+        let description = self
+            .client()
+            .create_snapshot(collection)
+            .await?
+            .snapshot_description
+            .ok_or_else(|| RagError::VectorStore("snapshot has no description".into()))?;
+
+        tokio::fs::create_dir_all(dir).await?;
+        let path = dir.join(&description.name);
+
+        let downloaded = async {
+            let response = self
+                .http_client
+                .get(format!(
+                    "{}/collections/{}/snapshots/{}",
+                    self.rest_url, collection, description.name
+                ))
+                .send()
+                .await?;
+            let response = check_status(response).await?;
+            save_verified(
+                response.bytes_stream(),
+                &path,
+                &description.name,
+                description.checksum.as_deref(),
+            )
+            .await
+        }
+        .await;
+        // the server copy was only needed for the download, it goes either way
+        let deleted = self
+            .client()
+            .delete_snapshot(DeleteSnapshotRequestBuilder::new(
+                collection,
+                description.name.clone(),
+            ))
+            .await;
+        let (size, checksum) = downloaded?;
+        deleted?;
+
+        tokio::fs::write(
+            checksum_path(&path),
+            format!("{}  {}\n", checksum, description.name),
+        )
+        .await?;
+
+        Ok(SnapshotFile {
+            collection: collection.to_string(),
+            path,
+            size,
+            checksum,
+        })
+    }
+
+    /// Restores `collection` from the snapshot file at `path`, replacing its current content.
+    /// The checksum is verified by Qdrant when given, or read from `<file>.sha256` if present.
+    pub async fn import_snapshot(
+        &self,
+        collection: &str,
+        path: &Path,
+        checksum: Option<&str>,
+    ) -> Result<()> {
+        let checksum = match checksum {
+            Some(checksum) => Some(checksum.to_string()),
+            None => tokio::fs::read_to_string(checksum_path(path))
+                .await
+                .ok()
+                .and_then(|line| line.split_whitespace().next().map(str::to_string)),
+        };
+
+        let file = tokio::fs::File::open(path).await?;
+        let size = file.metadata().await?.len();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| format!("{}.snapshot", collection));
+        let part = reqwest::multipart::Part::stream_with_length(
+            reqwest::Body::wrap_stream(ReaderStream::new(file)),
+            size,
+        )
+        .file_name(file_name);
+
+        let mut query = vec![
+            ("priority", "snapshot".to_string()),
+            ("wait", "true".to_string()),
+        ];
+        if let Some(checksum) = checksum {
+            query.push(("checksum", checksum));
+        }
+
+        let response = self
+            .http_client
+            .post(format!(
+                "{}/collections/{}/snapshots/upload",
+                self.rest_url, collection
+            ))
+            .query(&query)
+            .multipart(reqwest::multipart::Form::new().part("snapshot", part))
+            .send()
+            .await?;
+        check_status(response).await?;
+
+        Ok(())
+    }
+}
+
+/// Writes the snapshot `name` to `path` and returns its size and checksum. The file is removed
+/// again if writing fails or its checksum differs from the `expected` one.
+async fn save_verified<B, E>(
+    chunks: impl Stream<Item = std::result::Result<B, E>>,
+    path: &Path,
+    name: &str,
+    expected: Option<&str>,
+) -> Result<(u64, String)>
+where
+    B: AsRef<[u8]>,
+    RagError: From<E>,
+{
+    let saved = async {
+        let mut chunks = std::pin::pin!(chunks);
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            hasher.update(chunk.as_ref());
+            file.write_all(chunk.as_ref()).await?;
+            size += chunk.as_ref().len() as u64;
+        }
+        file.sync_all().await?;
+
+        let checksum = hex(&hasher.finalize());
+        match expected {
+            Some(expected) if expected != checksum => Err(RagError::VectorStore(format!(
+                "snapshot {} has checksum {}, server reported {}",
+                name, checksum, expected
+            ))),
+            _ => Ok((size, checksum)),
+        }
+    }
+    .await;
+
+    if saved.is_err() {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    saved
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".sha256");
+    path.with_file_name(file_name)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn mismatching_download_is_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("docs.snapshot");
+        let chunks = || futures::stream::iter([Ok::<_, std::io::Error>(b"snapshot".to_vec())]);
+        let checksum = hex(&Sha256::digest(b"snapshot"));
+
+        let result = save_verified(chunks(), &path, "docs.snapshot", Some("00")).await;
+        assert!(matches!(result, Err(RagError::VectorStore(_))));
+        assert!(!path.exists());
+
+        let (size, saved) = save_verified(chunks(), &path, "docs.snapshot", Some(&checksum))
+            .await
+            .unwrap();
+        assert_eq!((size, saved), (8, checksum));
+        assert!(path.exists());
+    }
+}
@@ -0,0 +1,267 @@
+use super::config::{Distance, RagConfig};
+use super::error::{RagError, Result};
+use super::ingest::ChunkSource;
+use super::rag::RetrievedChunk;
+use async_trait::async_trait;
+use qdrant_client::qdrant::{
+    point_id::PointIdOptions, vectors_config, CreateCollectionBuilder,
+    CreateFieldIndexCollectionBuilder, FieldType, PointId, PointStruct, SearchPointsBuilder,
+    UpsertPointsBuilder, VectorParamsBuilder,
+};
+use qdrant_client::{Payload, Qdrant, QdrantError};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Number of points written per upsert request
+pub const UPSERT_BATCH_SIZE: usize = 256;
+
+/// A chunk and its embedding as written to the vector store
+#[derive(Debug, Clone)]
+pub struct VectorPoint {
+    pub id: String,
+    pub vector: Vec<f32>,
+    pub text: String,
+    pub source: Option<ChunkSource>,
+}
+
+/// Storage and similarity search of embedded chunks
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Creates `collection` unless it exists. An existing collection must store vectors of
+    /// `vector_size` compared by `distance`.
+    async fn ensure_collection(
+        &self,
+        collection: &str,
+        vector_size: u64,
+        distance: Distance,
+    ) -> Result<()>;
+
+    /// Inserts the points or replaces the ones with the same id
+    async fn upsert(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()>;
+
+    /// Returns the `limit` points closest to `vector`, best first
+    async fn search(
+        &self,
+        collection: &str,
+        vector: Vec<f32>,
+        limit: u64,
+    ) -> Result<Vec<RetrievedChunk>>;
+
+    async fn list_collections(&self) -> Result<Vec<String>>;
+
+    /// Deletes `collection` and its points, returns whether it existed
+    async fn delete_collection(&self, collection: &str) -> Result<bool>;
+}
+
+impl From<QdrantError> for RagError {
+    fn from(e: QdrantError) -> Self {
+        RagError::VectorStore(e.to_string())
+    }
+}
+
+impl From<Distance> for qdrant_client::qdrant::Distance {
+    fn from(distance: Distance) -> Self {
+        match distance {
+            Distance::Cosine => qdrant_client::qdrant::Distance::Cosine,
+            Distance::Euclid => qdrant_client::qdrant::Distance::Euclid,
+            Distance::Dot => qdrant_client::qdrant::Distance::Dot,
+            Distance::Manhattan => qdrant_client::qdrant::Distance::Manhattan,
+        }
+    }
+}
+
+/// `VectorStore` backed by Qdrant's gRPC API
+#[derive(Clone)]
+pub struct QdrantStore {
+    client: Qdrant,
+    /// REST endpoint of the same instance, snapshots are transferred over HTTP
+    pub(crate) rest_url: String,
+    pub(crate) http_client: reqwest::Client,
+}
+
+impl QdrantStore {
+    /// Connects lazily to the gRPC endpoint at `url`, e.g. `http://localhost:6334`, and
+    /// expects the REST API on the default port
+    pub fn new(url: &str) -> Result<Self> {
+        Self::with_rest_url(url, "http://localhost:6333")
+    }
+
+    pub fn with_rest_url(url: &str, rest_url: &str) -> Result<Self> {
+        Ok(Self {
+            client: Qdrant::from_url(url).build()?,
+            rest_url: rest_url.trim_end_matches('/').to_string(),
+            http_client: reqwest::Client::new(),
+        })
+    }
+
+    pub fn from_config(config: &RagConfig) -> Result<Self> {
+        Self::with_rest_url(&config.qdrant_url, &config.qdrant_rest_url)
+    }
+
+    pub fn client(&self) -> &Qdrant {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl VectorStore for QdrantStore {
+    async fn ensure_collection(
+        &self,
+        collection: &str,
+        vector_size: u64,
+        distance: Distance,
+    ) -> Result<()> {
+        if self.client.collection_exists(collection).await? {
+            let info = self.client.collection_info(collection).await?;
+            let params = info
+                .result
+                .and_then(|info| info.config)
+                .and_then(|config| config.params)
+                .and_then(|params| params.vectors_config)
+                .and_then(|vectors| vectors.config)
+                .and_then(|config| match config {
+                    vectors_config::Config::Params(params) => Some(params),
+                    vectors_config::Config::ParamsMap(_) => None,
+                });
+            let Some(params) = params else {
+                return Ok(());
+            };
+            if params.size != vector_size {
+                return Err(RagError::VectorStore(format!(
+                    "collection {} stores vectors of size {}, ours have {}",
+                    collection, params.size, vector_size
+                )));
+            }
+            let expected = qdrant_client::qdrant::Distance::from(distance);
+            if params.distance != expected as i32 {
+                return Err(RagError::VectorStore(format!(
+                    "collection {} compares vectors by {:?}, ours use {:?}",
+                    collection,
+                    qdrant_client::qdrant::Distance::try_from(params.distance)
+                        .unwrap_or(qdrant_client::qdrant::Distance::UnknownDistance),
+                    expected
+                )));
+            }
+            return Ok(());
+        }
+
+        self.client
+            .create_collection(CreateCollectionBuilder::new(collection).vectors_config(
+                VectorParamsBuilder::new(
+                    vector_size,
+                    qdrant_client::qdrant::Distance::from(distance),
+                ),
+            ))
+            .await?;
+
+        // Lets searches be filtered by the document a chunk came from
+        self.client
+            .create_field_index(
+                CreateFieldIndexCollectionBuilder::new(collection, "path", FieldType::Keyword)
+                    .wait(true),
+            )
+            .await?;
+        self.client
+            .create_field_index(
+                CreateFieldIndexCollectionBuilder::new(collection, "page", FieldType::Integer)
+                    .wait(true),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn upsert(&self, collection: &str, points: Vec<VectorPoint>) -> Result<()> {
+        let points = points
+            .into_iter()
+            .map(|point| {
+                let payload = Payload::try_from(serde_json::json!({
+                    "text": point.text,
+                    "path": point.source.as_ref().map(|source| source.path.display().to_string()),
+                    "page": point.source.as_ref().and_then(|source| source.page),
+                    "offset": point.source.as_ref().map(|source| source.offset),
+                }))
+                .map_err(|e| RagError::Internal(e.to_string()))?;
+                Ok(PointStruct::new(point.id, point.vector, payload))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.client
+            .upsert_points_chunked(
+                UpsertPointsBuilder::new(collection, points).wait(true),
+                UPSERT_BATCH_SIZE,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        collection: &str,
+        vector: Vec<f32>,
+        limit: u64,
+    ) -> Result<Vec<RetrievedChunk>> {
+        let response = self
+            .client
+            .search_points(SearchPointsBuilder::new(collection, vector, limit).with_payload(true))
+            .await?;
+
+        Ok(response
+            .result
+            .into_iter()
+            .filter_map(|point| {
+                let payload: HashMap<String, serde_json::Value> = point
+                    .payload
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into_json()))
+                    .collect();
+
+                let source = payload
+                    .get("path")
+                    .and_then(|path| path.as_str())
+                    .map(|path| ChunkSource {
+                        path: PathBuf::from(path),
+                        page: payload
+                            .get("page")
+                            .and_then(|page| page.as_u64())
+                            .map(|page| page as u32),
+                        offset: payload
+                            .get("offset")
+                            .and_then(|offset| offset.as_u64())
+                            .unwrap_or_default() as usize,
+                    });
+
+                Some(RetrievedChunk {
+                    id: point_id_to_string(point.id?)?,
+                    score: point.score,
+                    text: payload.get("text")?.as_str()?.to_string(),
+                    source,
+                })
+            })
+            .collect())
+    }
+
+    async fn list_collections(&self) -> Result<Vec<String>> {
+        let response = self.client.list_collections().await?;
+        Ok(response
+            .collections
+            .into_iter()
+            .map(|collection| collection.name)
+            .collect())
+    }
+
+    async fn delete_collection(&self, collection: &str) -> Result<bool> {
+        if !self.client.collection_exists(collection).await? {
+            return Ok(false);
+        }
+        Ok(self.client.delete_collection(collection).await?.result)
+    }
+}
+
+fn point_id_to_string(id: PointId) -> Option<String> {
+    match id.point_id_options? {
+        PointIdOptions::Num(num) => Some(num.to_string()),
+        PointIdOptions::Uuid(uuid) => Some(uuid),
+    }
+}
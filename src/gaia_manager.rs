@@ -1,18 +1,128 @@
-use crate::types::{GaiaError, GaiaNodeConfig, GaiaNodeStatus, NodeInfo, Result};
-use blueprint_sdk::logging::{error, info, warn};
-use std::path::PathBuf;
+use crate::metrics::METRICS;
+use crate::node_logs::{LogStream, NodeLogs};
+use crate::node_metrics::{self, NodeMetrics};
+use crate::node_state::{NodeRecord, NodeStateStore};
+use crate::types::{
+    GaiaError, GaiaNodeConfig, GaiaNodeStatus, NodeInfo, ReadinessProbe, ResourceUsage,
+    RestartPolicy, Result, ShutdownPolicy,
+};
+use blueprint_sdk::logging::{debug, error, info, warn};
+use nix::sys::signal::{kill, killpg, Signal};
+use nix::unistd::Pid;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::Mutex; // Added import for tokio::process::Child
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// How often the supervisor checks whether the node process is still alive
+const SUPERVISOR_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a stopping node is checked for processes still alive
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How far the start time of a recorded pid may be off before the pid is considered reused
+const PID_START_TIME_TOLERANCE_SECS: u64 = 5;
+
+/// Processes running a node
+enum NodeProcess {
+    /// `gaianet start`, which daemonizes the node's services and exits once they are launched
+    Launcher(Child),
+    /// Services the launcher left running, found through the `*.pid` files of the data dir
+    Services(Vec<Pid>),
+}
+
+impl NodeProcess {
+    /// Pids to account the node's resource usage to
+    fn pids(&self) -> Vec<Pid> {
+        match self {
+            NodeProcess::Launcher(child) => child
+                .id()
+                .map(|pid| Pid::from_raw(pid as i32))
+                .into_iter()
+                .collect(),
+            NodeProcess::Services(pids) => pids.clone(),
+        }
+    }
+
+    /// Describes how the node ended once it failed, `None` while it is still running. A
+    /// launcher exiting successfully hands the node over to the services it started.
+    async fn poll(&mut self, data_path: &Path) -> Option<String> {
+        match self {
+            NodeProcess::Launcher(child) => match child.try_wait() {
+                Ok(None) => None,
+                Ok(Some(status)) if status.success() => {
+                    let pids = service_pids(data_path).await;
+                    if pids.is_empty() {
+                        return Some("gaianet start exited without any service running".into());
+                    }
+                    debug!("gaianet start handed the node over to services {:?}", pids);
+                    *self = NodeProcess::Services(pids);
+                    None
+                }
+                Ok(Some(status)) => Some(format!("gaianet start exited with {}", status)),
+                Err(e) => Some(format!("failed to poll gaianet start: {}", e)),
+            },
+            NodeProcess::Services(pids) => pids
+                .iter()
+                .find(|pid| !is_running(**pid))
+                .map(|pid| format!("service process {} exited", pid)),
+        }
+    }
+
+    /// Kills the launcher together with its process group, or the services it left running
+    fn kill(&mut self) {
+        match self {
+            NodeProcess::Launcher(child) => {
+                if let Some(pid) = child.id() {
+                    let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
+                }
+                let _ = child.start_kill();
+            }
+            NodeProcess::Services(pids) => send_signal(None, pids, Signal::SIGKILL),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct GaiaNodeManager {
     // holds the node process representing the gaia node
-    node_process: Arc<Mutex<Option<Child>>>,
+    node_process: Arc<Mutex<Option<NodeProcess>>>,
     // manages the node states
     status: Arc<Mutex<GaiaNodeStatus>>,
+    // configuration the node was last started with
+    config: Arc<Mutex<Option<GaiaNodeConfig>>>,
     node_path: PathBuf,
+    // how the supervisor restarts a crashed node
+    restart_policy: RestartPolicy,
+    // how the supervisor decides the node finished starting
+    readiness_probe: ReadinessProbe,
+    // how stop() escalates when the node doesn't exit
+    shutdown_policy: ShutdownPolicy,
+    http_client: reqwest::Client,
+    // background task watching the node process
+    supervisor: Arc<Mutex<Option<JoinHandle<()>>>>,
+    // set while the node is being stopped on purpose so its exit isn't treated as a crash
+    stop_requested: Arc<AtomicBool>,
+    // number of restarts performed by the supervisor since the last start()
+    restarts: Arc<AtomicU32>,
+    // when the node last became ready, cleared once it stops or fails
+    running_since: Arc<Mutex<Option<Instant>>>,
+    // on-chain task the node serves, attached to its log lines
+    task_id: Option<u32>,
+    // last lines written by the node process
+    logs: NodeLogs,
+    // recent resource usage samples of the node
+    metrics: NodeMetrics,
+    // where the node's pid is recorded so a restarted service can re-attach to it
+    state: Option<NodeStateStore>,
+    // shared by the clones handed out to callers, the last of them shuts the node down on
+    // drop. The supervisor's copy holds none so it doesn't keep the node alive.
+    owner: Option<Arc<()>>,
 }
 
 impl GaiaNodeManager {
@@ -20,11 +130,72 @@ impl GaiaNodeManager {
         let node_path = Self::find_node_binary()?;
         blueprint_sdk::logging::info!("node path: {:?}", node_path.clone());
 
-        Ok(Self {
+        Ok(Self::with_binary(node_path))
+    }
+
+    /// Manages nodes through the gaianet CLI at `node_path` instead of the installed one
+    pub fn with_binary(node_path: PathBuf) -> Self {
+        Self {
             node_process: Arc::new(Mutex::new(None)),
             status: Arc::new(Mutex::new(GaiaNodeStatus::Stopped)),
+            config: Arc::new(Mutex::new(None)),
             node_path,
-        })
+            restart_policy: RestartPolicy::default(),
+            readiness_probe: ReadinessProbe::default(),
+            shutdown_policy: ShutdownPolicy::default(),
+            http_client: reqwest::Client::new(),
+            supervisor: Arc::new(Mutex::new(None)),
+            stop_requested: Arc::new(AtomicBool::new(false)),
+            restarts: Arc::new(AtomicU32::new(0)),
+            running_since: Arc::new(Mutex::new(None)),
+            task_id: None,
+            logs: NodeLogs::default(),
+            metrics: NodeMetrics::default(),
+            state: None,
+            owner: Some(Arc::new(())),
+        }
+    }
+
+    /// Replaces the default timeouts used when stopping the node
+    pub fn with_shutdown_policy(mut self, shutdown_policy: ShutdownPolicy) -> Self {
+        self.shutdown_policy = shutdown_policy;
+        self
+    }
+
+    /// Tags the node's log lines with the task it serves
+    pub fn with_task_id(mut self, task_id: u32) -> Self {
+        self.task_id = Some(task_id);
+        self
+    }
+
+    /// Replaces the default log buffer, e.g. to keep more lines
+    pub fn with_logs(mut self, logs: NodeLogs) -> Self {
+        self.logs = logs;
+        self
+    }
+
+    /// Replaces the default resource usage history, e.g. to keep more samples
+    pub fn with_metrics(mut self, metrics: NodeMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Records the node process in `state` while it runs, requires a task id
+    pub fn with_state_store(mut self, state: NodeStateStore) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Replaces the default restart policy used when the node crashes
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = restart_policy;
+        self
+    }
+
+    /// Replaces the default readiness probe used to move the node from `Starting` to `Running`
+    pub fn with_readiness_probe(mut self, readiness_probe: ReadinessProbe) -> Self {
+        self.readiness_probe = readiness_probe;
+        self
     }
 
     fn find_node_binary() -> Result<PathBuf> {
@@ -47,130 +218,897 @@ impl GaiaNodeManager {
         }
     }
 
-    pub async fn start(&self, _config: GaiaNodeConfig) -> Result<()> {
+    pub async fn start(&self, config: GaiaNodeConfig) -> Result<()> {
         info!("Starting Gaianet Node...");
-        let path = which::which("gaianet").unwrap();
-        info!("Running Gaia at path: {:?}", path);
+        {
+            let status_lock = self.status.lock().await;
+            if matches!(
+                *status_lock,
+                GaiaNodeStatus::Starting | GaiaNodeStatus::Running
+            ) {
+                return Err(GaiaError::InvalidState(format!(
+                    "node is already {}",
+                    status_lock
+                )));
+            }
+        }
 
-        let mut command: Child = Command::new(path)
-            .arg("start")
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .unwrap();
-        info!("Running command: {:?}", command);
+        config.validate()?;
 
-        {
-            let mut status_lock = self.status.lock().await;
-            *status_lock = GaiaNodeStatus::Starting;
-        }
-
-        let stdout = command
-            .stdout
-            .take()
-            .expect("Failed to capture child stdout");
-
-        let _stdout_handle = tokio::spawn(async move {
-            use tokio::io::{AsyncBufReadExt, BufReader};
-            let mut reader = BufReader::new(stdout);
-            let mut line = String::new();
-            while let Ok(bytes_read) = reader.read_line(&mut line).await {
-                if bytes_read == 0 {
-                    break; // EOF
-                }
-                // Print the child's stdout line to our own stdout
-                println!("[Child stdout] {}", line.trim_end());
-                line.clear();
-            }
-        });
-
-        let stderr = command
-            .stderr
-            .take()
-            .expect("Failed to capture child stderr");
-
-        // Read stderr in another task
-        let _stderr_handle = tokio::spawn(async move {
-            use tokio::io::{AsyncBufReadExt, BufReader};
-            let mut reader = BufReader::new(stderr);
-            let mut line = String::new();
-            while let Ok(bytes_read) = reader.read_line(&mut line).await {
-                if bytes_read == 0 {
-                    break; // EOF
-                }
-                // Print the child's stderr line to our own stderr
-                eprintln!("[Child stderr] {}", line.trim_end());
-                line.clear();
-            }
-        });
-
-        // let _ = tokio::join!(stdout_handle, stderr_handle);
-        {
-            let mut status_lock = self.status.lock().await;
-            *status_lock = GaiaNodeStatus::Running;
-            info!("Node status: {}", status_lock.to_string());
+        self.stop_requested.store(false, Ordering::SeqCst);
+        self.restarts.store(0, Ordering::SeqCst);
+        *self.config.lock().await = Some(config.clone());
+        self.set_status(GaiaNodeStatus::Starting).await;
+
+        if let Err(e) = self.prepare_data_dir(&config).await {
+            self.set_status(GaiaNodeStatus::Error(e.to_string())).await;
+            return Err(e);
+        }
+
+        if let Err(e) = self.spawn_node(&config).await {
+            self.set_status(GaiaNodeStatus::Error(e.to_string())).await;
+            return Err(e);
         }
 
+        self.spawn_supervisor(config).await;
+
         Ok(())
     }
 
-    pub async fn stop(&self) -> Result<()> {
-        info!("Inside stop() function!");
-        {
-            let status_lock = self.status.lock().await;
-            info!("Node status: {}", status_lock.to_string());
+    /// Takes over the node described by `record`, spawned by a previous run of the service.
+    /// A node whose services are still alive is supervised as if it had been started by this
+    /// manager, otherwise it is started again from its recorded config. Output of an adopted
+    /// node can't be captured.
+    pub async fn adopt(&self, record: NodeRecord) {
+        self.stop_requested.store(false, Ordering::SeqCst);
+        self.restarts.store(0, Ordering::SeqCst);
+        *self.config.lock().await = Some(record.config.clone());
+
+        let pids = service_pids(&record.config.data_path()).await;
+        if pids.is_empty() {
+            // a launcher interrupted halfway would keep starting services nobody watches
+            if is_recorded_process(&record) {
+                let _ = killpg(Pid::from_raw(record.pid as i32), Signal::SIGKILL);
+            }
+            warn!(
+                task_id = self.task_id,
+                "Gaia node services exited while the service was down, starting the node again"
+            );
+            if let Err(e) = self.start(record.config).await {
+                error!(task_id = self.task_id, "Failed to restart Gaia node: {}", e);
+            }
+            return;
+        }
+
+        info!(
+            task_id = self.task_id,
+            "Re-attached to Gaia node services {:?}", pids
+        );
+        *self.node_process.lock().await = Some(NodeProcess::Services(pids));
+        self.set_status(GaiaNodeStatus::Starting).await;
+        self.spawn_supervisor(record.config).await;
+    }
+
+    /// Watches the node process for the rest of its life and restarts it on crashes
+    async fn spawn_supervisor(&self, config: GaiaNodeConfig) {
+        let mut manager = self.clone();
+        manager.owner = None;
+        let handle = tokio::spawn(async move { manager.supervise(config).await });
+        if let Some(previous) = self.supervisor.lock().await.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    /// Runs `gaianet init` for the configured network unless the data dir already holds an
+    /// initialized node, then applies the configured port
+    async fn prepare_data_dir(&self, config: &GaiaNodeConfig) -> Result<()> {
+        let data_path = config.data_path();
+        if !data_path.join("config.json").exists() {
+            self.init_data_dir(config).await?;
+        }
+
+        if let Some(port) = config.port {
+            let base = data_path.to_string_lossy();
+            let port = port.to_string();
+            self.run_cli(&["config", "--base", &base, "--port", &port])
+                .await?;
         }
 
-        let path = which::which("gaianet").unwrap();
-        info!("Running Gaia at path: {:?}", path);
+        Ok(())
+    }
+
+    async fn init_data_dir(&self, config: &GaiaNodeConfig) -> Result<()> {
+        let data_path = config.data_path();
+        info!(
+            "Initializing Gaia data dir {} for network {}",
+            data_path.display(),
+            config.network
+        );
+        tokio::fs::create_dir_all(&data_path)
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", data_path.display(), e)))?;
+
+        let base = data_path.to_string_lossy();
+        let mut args = vec!["init", "--base", &base];
+        if let Some(preset) = config.network_preset() {
+            args.extend(["--config", preset]);
+        }
+        let output = self.run_cli(&args).await?;
+        if config.verbose {
+            info!("gaianet init: {}", output.trim_end());
+        }
+
+        Ok(())
+    }
+
+    /// Spawns `gaianet start` and keeps ownership of the child process
+    async fn spawn_node(&self, config: &GaiaNodeConfig) -> Result<()> {
+        info!("Running Gaia at path: {:?}", self.node_path);
+
+        let mut command = Command::new(&self.node_path);
+        command.arg("start").arg("--base").arg(config.data_path());
+        if config.verbose {
+            command.env("RUST_LOG", "debug");
+        }
 
-        let mut command: Child = Command::new(path)
-            .arg("stop")
+        // own process group, so the services gaianet starts can be signalled together
+        let mut child: Child = command
+            .process_group(0)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .unwrap();
+            .map_err(|e| GaiaError::CommandFailed(format!("failed to spawn gaianet: {}", e)))?;
+        info!(
+            "Running command: {} start --base {}",
+            self.node_path.display(),
+            config.data_path().display()
+        );
+
+        self.set_status(GaiaNodeStatus::Starting).await;
+
+        let node = config.data_path().display().to_string();
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_output(
+                stdout,
+                LogStream::Stdout,
+                self.logs.clone(),
+                self.task_id,
+                node.clone(),
+            ));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_output(
+                stderr,
+                LogStream::Stderr,
+                self.logs.clone(),
+                self.task_id,
+                node,
+            ));
+        }
+
+        if let Some(pid) = child.id() {
+            self.save_record(pid, config).await;
+        }
+        *self.node_process.lock().await = Some(NodeProcess::Launcher(child));
+
+        Ok(())
+    }
+
+    /// Persists the node's pid, a failure only costs the ability to re-attach after a restart
+    async fn save_record(&self, pid: u32, config: &GaiaNodeConfig) {
+        let (Some(state), Some(task_id)) = (&self.state, self.task_id) else {
+            return;
+        };
+        let record = NodeRecord {
+            task_id,
+            pid,
+            config: config.clone(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        };
+        if let Err(e) = state.save(&record).await {
+            warn!("Failed to record the Gaia node of task {}: {}", task_id, e);
+        }
+    }
+
+    /// Waits for the node to become ready, then for it to exit, and restarts it according
+    /// to the restart policy
+    async fn supervise(&self, config: GaiaNodeConfig) {
+        let mut attempt = 0;
+        loop {
+            let started_at = Instant::now();
+            let failure = match self.wait_until_ready(&config).await {
+                Ok(()) => {
+                    METRICS.node_started(started_at.elapsed());
+                    {
+                        let mut status_lock = self.status.lock().await;
+                        *status_lock = GaiaNodeStatus::Running;
+                        info!("Node status: {}", status_lock.to_string());
+                    }
+                    *self.running_since.lock().await = Some(Instant::now());
+                    let exit = self.wait_for_exit(&config).await;
+                    format!("node exited unexpectedly: {}", exit)
+                }
+                Err(reason) => reason,
+            };
+            if self.stop_requested.load(Ordering::SeqCst) {
+                return;
+            }
+
+            warn!("Gaia node failed: {}", failure);
+            self.set_status(GaiaNodeStatus::Error(failure)).await;
+            // a node that never became ready may still be running, get rid of it before retrying
+            if let Some(mut process) = self.node_process.lock().await.take() {
+                process.kill();
+            }
+            // so would services left behind by a failed launcher, holding on to the node's ports
+            let leftovers = service_pids(&config.data_path()).await;
+            send_signal(None, &leftovers, Signal::SIGKILL);
+
+            if started_at.elapsed() >= self.restart_policy.reset_after {
+                attempt = 0;
+            }
+
+            loop {
+                if attempt >= self.restart_policy.max_retries {
+                    error!(
+                        "Gaia node failed {} restart attempts, giving up",
+                        self.restart_policy.max_retries
+                    );
+                    return;
+                }
+
+                let delay = self.restart_policy.backoff(attempt);
+                attempt += 1;
+                info!(
+                    "Restarting Gaia node in {:?} (attempt {}/{})",
+                    delay, attempt, self.restart_policy.max_retries
+                );
+                tokio::time::sleep(delay).await;
+                if self.stop_requested.load(Ordering::SeqCst) {
+                    return;
+                }
+
+                match self.spawn_node(&config).await {
+                    Ok(()) => {
+                        self.restarts.fetch_add(1, Ordering::SeqCst);
+                        if let Some(task_id) = self.task_id {
+                            METRICS.node_restarted(task_id);
+                        }
+                        break;
+                    }
+                    Err(e) => {
+                        error!("Failed to restart Gaia node: {}", e);
+                        self.set_status(GaiaNodeStatus::Error(e.to_string())).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Probes the node's HTTP endpoint until it answers, the node fails or the startup timeout
+    /// elapses
+    async fn wait_until_ready(&self, config: &GaiaNodeConfig) -> std::result::Result<(), String> {
+        let probe = &self.readiness_probe;
+        let url = format!("{}/models", config.api_url());
+        let data_path = config.data_path();
+        let deadline = Instant::now() + probe.startup_timeout;
+        loop {
+            {
+                let mut node_process = self.node_process.lock().await;
+                let Some(process) = node_process.as_mut() else {
+                    return Err("node process is no longer tracked".to_string());
+                };
+                if let Some(exit) = process.poll(&data_path).await {
+                    node_process.take();
+                    return Err(format!("node exited during startup: {}", exit));
+                }
+            }
+
+            match self
+                .http_client
+                .get(&url)
+                .timeout(probe.interval)
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => {
+                    info!("Gaia node is ready at {}", url);
+                    return Ok(());
+                }
+                Ok(response) => {
+                    debug!("Readiness probe returned {}", response.status());
+                }
+                Err(e) => {
+                    debug!("Readiness probe failed: {}", e);
+                }
+            }
+
+            if Instant::now() >= deadline {
+                return Err("startup timed out".to_string());
+            }
+            tokio::time::sleep(probe.interval).await;
+        }
+    }
+
+    /// Polls the node's processes until the node fails, returning a description of how it
+    /// ended
+    async fn wait_for_exit(&self, config: &GaiaNodeConfig) -> String {
+        let data_path = config.data_path();
+        loop {
+            {
+                let mut node_process = self.node_process.lock().await;
+                let Some(process) = node_process.as_mut() else {
+                    return "node process is no longer tracked".to_string();
+                };
+                if let Some(exit) = process.poll(&data_path).await {
+                    node_process.take();
+                    return exit;
+                }
+            }
+            tokio::time::sleep(SUPERVISOR_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn set_status(&self, status: GaiaNodeStatus) {
+        if status != GaiaNodeStatus::Running {
+            *self.running_since.lock().await = None;
+        }
+        let mut status_lock = self.status.lock().await;
+        *status_lock = status;
+    }
 
-        let stdout = command
-            .stdout
-            .take()
-            .expect("Failed to capture child stdout");
+    /// Stops the node and waits until its processes are gone: `gaianet stop` runs first,
+    /// survivors get SIGTERM and then SIGKILL. The node ends up `Stopped`, or in `Error` if
+    /// some process could not be killed.
+    pub async fn stop(&self) -> Result<()> {
+        info!(task_id = self.task_id, "Stopping Gaia node");
+        let stop_started = Instant::now();
+        // the exit that follows is intentional, keep the supervisor from restarting the node
+        self.stop_requested.store(true, Ordering::SeqCst);
+        if let Some(supervisor) = self.supervisor.lock().await.take() {
+            supervisor.abort();
+        }
 
-        let _stdout_handle = tokio::spawn(async move {
-            use tokio::io::{AsyncBufReadExt, BufReader};
-            let mut reader = BufReader::new(stdout);
-            let mut line = String::new();
-            while let Ok(bytes_read) = reader.read_line(&mut line).await {
-                if bytes_read == 0 {
-                    break; // EOF
+        let config = self.config.lock().await.clone();
+        let mut pids = match &config {
+            Some(config) => service_pids(&config.data_path()).await,
+            None => Vec::new(),
+        };
+        let mut launcher = None;
+        match self.node_process.lock().await.take() {
+            Some(NodeProcess::Launcher(child)) => launcher = Some(child),
+            Some(NodeProcess::Services(services)) => {
+                for pid in services {
+                    if !pids.contains(&pid) {
+                        pids.push(pid);
+                    }
                 }
-                // Print the child's stdout line to our own stdout
-                println!("[Child stdout] {}", line.trim_end());
-                line.clear();
             }
-        });
+            None => {}
+        }
+
+        if let Some(config) = &config {
+            let base = config.data_path().to_string_lossy().into_owned();
+            let stop_command = ["stop", "--base", &base];
+            let timeout = self.shutdown_policy.stop_timeout;
+            match tokio::time::timeout(timeout, self.run_cli(&stop_command)).await {
+                Ok(Ok(output)) => debug!("gaianet stop: {}", output.trim_end()),
+                Ok(Err(e)) => warn!("gaianet stop failed: {}", e),
+                Err(_) => warn!("gaianet stop did not finish within {:?}", timeout),
+            }
+        }
+
+        let mut stopped = self.wait_until_gone(&mut launcher, &pids).await;
+        for signal in [Signal::SIGTERM, Signal::SIGKILL] {
+            if stopped {
+                break;
+            }
+            warn!("Gaia node processes are still alive, sending {}", signal);
+            // the launcher's group is only signalled while the launcher is alive to hold its id
+            let group = launcher
+                .as_ref()
+                .and_then(Child::id)
+                .map(|pid| Pid::from_raw(pid as i32));
+            send_signal(group, &pids, signal);
+            stopped = self.wait_until_gone(&mut launcher, &pids).await;
+        }
+
+        if !stopped {
+            let reason = "node processes survived SIGKILL".to_string();
+            self.set_status(GaiaNodeStatus::Error(reason.clone())).await;
+            return Err(GaiaError::CommandFailed(reason));
+        }
 
-        info!("Running command: {:?}", command);
+        self.set_status(GaiaNodeStatus::Stopped).await;
+        METRICS.node_stopped(stop_started.elapsed());
+        if let (Some(state), Some(task_id)) = (&self.state, self.task_id) {
+            if let Err(e) = state.remove(task_id).await {
+                warn!("Failed to forget the Gaia node of task {}: {}", task_id, e);
+            }
+        }
+        info!(task_id = self.task_id, "Gaia node stopped");
         Ok(())
     }
 
+    /// Polls until the launcher and the node's services exited, for at most the shutdown
+    /// grace period. Reaps the launcher so it doesn't linger as a zombie.
+    async fn wait_until_gone(&self, launcher: &mut Option<Child>, pids: &[Pid]) -> bool {
+        let deadline = Instant::now() + self.shutdown_policy.grace_period;
+        loop {
+            if let Some(child) = launcher {
+                if !matches!(child.try_wait(), Ok(None)) {
+                    launcher.take();
+                }
+            }
+
+            let alive = launcher.is_some() || pids.iter().any(|pid| is_running(*pid));
+            if !alive {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+    }
+
     pub async fn get_status(&self) -> GaiaNodeStatus {
         let status = self.status.lock().await;
         status.clone()
     }
 
-    // pub async fn get_info(&self) -> Result<NodeInfo> {
-    // }
+    /// Whether a supervisor still watches the node or is retrying to start it, false once it
+    /// gave up
+    pub async fn is_supervised(&self) -> bool {
+        self.supervisor
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|supervisor| !supervisor.is_finished())
+    }
+
+    /// How long the node has been running since it last became ready
+    pub async fn uptime(&self) -> Option<Duration> {
+        self.running_since.lock().await.map(|since| since.elapsed())
+    }
+
+    /// Ids of the models served by the node's OpenAI-compatible API
+    pub async fn served_models(&self) -> Result<Vec<String>> {
+        let config = self.config.lock().await.clone().unwrap_or_default();
+        let url = format!("{}/models", config.api_url());
+
+        let response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| GaiaError::CommandFailed(format!("GET {}: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(GaiaError::CommandFailed(format!(
+                "GET {} returned {}",
+                url,
+                response.status()
+            )));
+        }
+
+        let models: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| GaiaError::ParseError(format!("invalid models response: {}", e)))?;
+        Ok(models["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Configuration the node was last started with
+    pub async fn get_config(&self) -> Option<GaiaNodeConfig> {
+        self.config.lock().await.clone()
+    }
+
+    /// Last lines written by the node process
+    pub fn logs(&self) -> &NodeLogs {
+        &self.logs
+    }
+
+    /// Recent resource usage samples of the node
+    pub fn metrics(&self) -> &NodeMetrics {
+        &self.metrics
+    }
+
+    /// Measures the resources used by the node's process tree, including the services listed
+    /// in its data dir, and by its data dir, and adds the sample to the history. `system`
+    /// must have been refreshed with CPU and memory usage. Stopped nodes aren't sampled.
+    pub async fn sample_resources(&self, system: &sysinfo::System) -> Option<ResourceUsage> {
+        if self.get_status().await == GaiaNodeStatus::Stopped {
+            return None;
+        }
+        let data_path = self.config.lock().await.as_ref()?.data_path();
+
+        let mut roots: Vec<sysinfo::Pid> = service_pids(&data_path)
+            .await
+            .into_iter()
+            .map(|pid| sysinfo::Pid::from_u32(pid.as_raw() as u32))
+            .collect();
+        let node_pids = self
+            .node_process
+            .lock()
+            .await
+            .as_ref()
+            .map(NodeProcess::pids)
+            .unwrap_or_default();
+        roots.extend(
+            node_pids
+                .into_iter()
+                .map(|pid| sysinfo::Pid::from_u32(pid.as_raw() as u32)),
+        );
+        let processes = node_metrics::process_tree_usage(system, &roots);
+        let disk_bytes = tokio::task::spawn_blocking(move || node_metrics::disk_usage(&data_path))
+            .await
+            .unwrap_or_default();
+
+        let usage = ResourceUsage {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            cpu_percent: processes.cpu_percent,
+            memory_bytes: processes.memory_bytes,
+            open_files: processes.open_files,
+            disk_bytes,
+            processes: processes.processes,
+        };
+        self.metrics.record(usage.clone());
+        Some(usage)
+    }
+
+    /// Number of times the node was restarted after crashing since it was last started
+    pub fn restart_count(&self) -> u32 {
+        self.restarts.load(Ordering::SeqCst)
+    }
+
+    /// Collects version, identity and resource usage of the node. Peers and sync progress
+    /// aren't exposed by gaianet and are left unset.
+    pub async fn get_info(&self) -> Result<NodeInfo> {
+        let config = self.config.lock().await.clone().unwrap_or_default();
+
+        let version = parse_version(&self.run_cli(&["--version"]).await?)?;
+        let node_id = self.read_node_id(&config.data_path()).await?;
+
+        Ok(NodeInfo {
+            version,
+            network: config.network,
+            node_id,
+            // not exposed by gaianet
+            peers: None,
+            sync_status: None,
+            resources: self.metrics.latest(),
+        })
+    }
+
+    /// Runs a one-shot gaianet subcommand and returns its stdout
+    async fn run_cli(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new(&self.node_path)
+            .args(args)
+            // a caller that gives up on the command (e.g. on timeout) shouldn't leave it behind
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| GaiaError::CommandFailed(format!("gaianet {}: {}", args.join(" "), e)))?;
+
+        if !output.status.success() {
+            return Err(GaiaError::CommandFailed(format!(
+                "gaianet {} exited with {}: {}",
+                args.join(" "),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Reads the node id (its address) from the data dir's `config.json`, falling back to
+    /// the output of `gaianet info`
+    async fn read_node_id(&self, data_dir: &Path) -> Result<String> {
+        if let Ok(raw) = tokio::fs::read_to_string(data_dir.join("config.json")).await {
+            let config: serde_json::Value = serde_json::from_str(&raw).map_err(|e| {
+                GaiaError::ParseError(format!("invalid {}/config.json: {}", data_dir.display(), e))
+            })?;
+            if let Some(address) = config["address"].as_str().filter(|a| !a.is_empty()) {
+                return Ok(address.to_string());
+            }
+        }
+
+        let base = data_dir.to_string_lossy();
+        let info = self.run_cli(&["info", "--base", &base]).await?;
+        info.lines()
+            .find_map(|line| line.split_once("Node ID:"))
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .map(|id| id.to_string())
+            .ok_or_else(|| GaiaError::ParseError("node id not found in gaianet info".into()))
+    }
+}
+
+/// Whether the process recorded for a node is still alive. A pid now used by a process that
+/// started at another time than the recorded node is treated as gone.
+fn is_recorded_process(record: &NodeRecord) -> bool {
+    let pid = sysinfo::Pid::from_u32(record.pid);
+    let mut system = sysinfo::System::new();
+    system.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[pid]),
+        true,
+        sysinfo::ProcessRefreshKind::nothing(),
+    );
+
+    system.process(pid).is_some_and(|process| {
+        process.status() != sysinfo::ProcessStatus::Zombie
+            && process.start_time().abs_diff(record.started_at) <= PID_START_TIME_TOLERANCE_SECS
+    })
+}
+
+/// Whether `pid` is still running, a zombie waiting to be reaped counts as gone
+fn is_running(pid: Pid) -> bool {
+    let pid = sysinfo::Pid::from_u32(pid.as_raw() as u32);
+    let mut system = sysinfo::System::new();
+    system.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[pid]),
+        true,
+        sysinfo::ProcessRefreshKind::nothing(),
+    );
+
+    system
+        .process(pid)
+        .is_some_and(|process| process.status() != sysinfo::ProcessStatus::Zombie)
+}
+
+/// Sends `signal` to the launcher's process group and to the node's services
+fn send_signal(group: Option<Pid>, pids: &[Pid], signal: Signal) {
+    if let Some(group) = group {
+        let _ = killpg(group, signal);
+    }
+    for pid in pids {
+        let _ = kill(*pid, signal);
+    }
+}
+
+/// Processes of the services gaianet started for the node, read from the `*.pid` files in its
+/// data dir. Pids that were reused by a process unrelated to the data dir are left out.
+async fn service_pids(data_path: &Path) -> Vec<Pid> {
+    let mut pid_files = Vec::new();
+    let mut pending = vec![data_path.to_path_buf()];
+    // gaianet keeps its pid files at most one level below the data dir
+    for _ in 0..2 {
+        for dir in std::mem::take(&mut pending) {
+            let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+                continue;
+            };
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.extension().is_some_and(|extension| extension == "pid") {
+                    pid_files.push(path);
+                }
+            }
+        }
+    }
+
+    let mut pids = Vec::new();
+    for file in pid_files {
+        let Ok(raw) = tokio::fs::read_to_string(&file).await else {
+            continue;
+        };
+        if let Ok(pid) = raw.trim().parse::<u32>() {
+            pids.push(sysinfo::Pid::from_u32(pid));
+        }
+    }
+    if pids.is_empty() {
+        return Vec::new();
+    }
+
+    let mut system = sysinfo::System::new();
+    system.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&pids),
+        true,
+        sysinfo::ProcessRefreshKind::nothing()
+            .with_cmd(sysinfo::UpdateKind::Always)
+            .with_cwd(sysinfo::UpdateKind::Always),
+    );
+
+    let data_dir = data_path.to_string_lossy();
+    pids.into_iter()
+        .filter(|pid| {
+            system.process(*pid).is_some_and(|process| {
+                process.cwd().is_some_and(|cwd| cwd.starts_with(data_path))
+                    || process
+                        .cmd()
+                        .iter()
+                        .any(|arg| arg.to_string_lossy().contains(data_dir.as_ref()))
+            })
+        })
+        .map(|pid| Pid::from_raw(pid.as_u32() as i32))
+        .collect()
+}
+
+/// Reads the node's output line by line into its log buffer and our own log, tagged with the
+/// task and data dir of the node
+async fn forward_output(
+    output: impl AsyncRead + Unpin,
+    stream: LogStream,
+    logs: NodeLogs,
+    task_id: Option<u32>,
+    node: String,
+) {
+    let mut lines = BufReader::new(output).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                match stream {
+                    LogStream::Stdout => info!(task_id, node = %node, "{}", line),
+                    LogStream::Stderr => warn!(task_id, node = %node, "{}", line),
+                }
+                logs.push(stream, line);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                debug!(task_id, node = %node, "Stopped reading node output: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Extracts the version from `gaianet --version` output, e.g. `GaiaNet CLI Tool v0.4.20`
+fn parse_version(output: &str) -> Result<String> {
+    let line = output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .ok_or_else(|| GaiaError::ParseError("empty gaianet --version output".into()))?;
+
+    Ok(line
+        .split_whitespace()
+        .find(|word| word.starts_with('v') && word[1..].starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(line)
+        .to_string())
 }
 
 impl Drop for GaiaNodeManager {
     fn drop(&mut self) {
-        // Try to stop the node if it's still running when the manager is dropped
-        if let Ok(mut node_process) = self.node_process.try_lock() {
-            if let Some(mut child) = node_process.take() {
-                let _ = child.kill(); // Ignore errors during drop
-                let _ = child.wait(); // Ignore errors during drop
+        // Clones of the manager are handed out to jobs and the API server, only the
+        // last one alive owns the node process
+        let Some(owner) = &self.owner else {
+            return;
+        };
+        if Arc::strong_count(owner) > 1 {
+            return;
+        }
+        if let Ok(mut supervisor) = self.supervisor.try_lock() {
+            if let Some(supervisor) = supervisor.take() {
+                supervisor.abort();
+            }
+        }
+        // Drop can't wait for a graceful stop, kill the node outright. Tokio reaps the child
+        // in the background once it exited.
+        match self.node_process.try_lock() {
+            Ok(mut node_process) => {
+                if let Some(mut process) = node_process.take() {
+                    process.kill();
+                }
+            }
+            // the aborted supervisor may still hold the lock until its task is dropped
+            Err(_) => {
+                let node_process = self.node_process.clone();
+                if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                    runtime.spawn(async move {
+                        if let Some(mut process) = node_process.lock().await.take() {
+                            process.kill();
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Stands in for gaianet: `start` daemonizes a service and records its pid the way gaianet
+    /// does, `stop` leaves it running so the manager has to escalate
+    const FAKE_GAIANET: &str = r#"#!/bin/sh
+if [ "$1" = start ]; then
+    cd "$3" || exit 1
+    nohup sleep 300 >/dev/null 2>&1 &
+    echo $! > llamaedge.pid
+fi
+exit 0
+"#;
+
+    /// A manager driving `FAKE_GAIANET` in `dir` and the config of an initialized node there
+    fn fake_node(dir: &Path) -> (GaiaNodeManager, GaiaNodeConfig) {
+        let data_dir = dir.join("node");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        // an initialized data dir, so start() doesn't run `gaianet init`
+        std::fs::write(data_dir.join("config.json"), "{}").unwrap();
+        let binary = dir.join("gaianet");
+        std::fs::write(&binary, FAKE_GAIANET).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let manager = GaiaNodeManager::with_binary(binary)
+            .with_readiness_probe(ReadinessProbe {
+                interval: Duration::from_millis(100),
+                startup_timeout: Duration::from_secs(60),
+            })
+            .with_shutdown_policy(ShutdownPolicy {
+                stop_timeout: Duration::from_secs(5),
+                grace_period: Duration::from_millis(500),
+            });
+        let config = GaiaNodeConfig {
+            data_dir: data_dir.to_string_lossy().into_owned(),
+            ..GaiaNodeConfig::default()
+        };
+        (manager, config)
+    }
+
+    /// Waits for the launcher to hand the node over to its daemonized service
+    async fn service_of(manager: &GaiaNodeManager) -> Pid {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Some(NodeProcess::Services(pids)) = &*manager.node_process.lock().await {
+                return pids[0];
             }
+            assert!(Instant::now() < deadline, "the launcher never handed off");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn stop_terminates_daemonized_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, config) = fake_node(dir.path());
+        manager.start(config).await.unwrap();
+
+        let service = service_of(&manager).await;
+        assert!(is_running(service));
+
+        manager.stop().await.unwrap();
+        assert!(!is_running(service));
+        assert_eq!(manager.get_status().await, GaiaNodeStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn dropping_a_supervised_manager_kills_its_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, config) = fake_node(dir.path());
+        manager.start(config).await.unwrap();
+
+        let service = service_of(&manager).await;
+        assert!(manager.is_supervised().await);
+
+        let handle = manager.clone();
+        drop(manager);
+        assert!(is_running(service), "a remaining clone keeps the node");
+        drop(handle);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while is_running(service) {
+            assert!(
+                Instant::now() < deadline,
+                "the service outlived its manager"
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
         }
     }
 }
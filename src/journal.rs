@@ -0,0 +1,173 @@
+use crate::types::{GaiaError, Result};
+use blueprint_sdk::logging::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Identifies a single delivery of an on-chain event
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EventKey {
+    pub block_hash: String,
+    pub log_index: u64,
+    pub task_id: u32,
+}
+
+impl fmt::Display for EventKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{} (task {})",
+            self.block_hash, self.log_index, self.task_id
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalEventKind {
+    NodeStarted,
+    NodeStopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    #[serde(flatten)]
+    pub key: EventKey,
+    pub kind: JournalEventKind,
+    /// Unix time (seconds) the event was applied at
+    pub applied_at: u64,
+}
+
+/// Append-only record of the on-chain events the jobs already applied, so events redelivered
+/// after a restart are not applied twice. Stored as one JSON entry per line.
+#[derive(Clone)]
+pub struct EventJournal {
+    path: PathBuf,
+    entries: Arc<Mutex<Vec<JournalEntry>>>,
+}
+
+impl EventJournal {
+    /// Opens the journal at `path`, creating it if it doesn't exist yet
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| GaiaError::Io(format!("{}: {}", parent.display(), e)))?;
+        }
+
+        let mut entries = Vec::new();
+        match tokio::fs::read_to_string(&path).await {
+            Ok(raw) => {
+                for (number, line) in raw.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str(line) {
+                        Ok(entry) => entries.push(entry),
+                        // a crash while appending can leave a torn last line behind
+                        Err(e) => warn!(
+                            "Skipping malformed journal line {} in {}: {}",
+                            number + 1,
+                            path.display(),
+                            e
+                        ),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(GaiaError::Io(format!("{}: {}", path.display(), e))),
+        }
+
+        Ok(Self {
+            path,
+            entries: Arc::new(Mutex::new(entries)),
+        })
+    }
+
+    /// Whether the event identified by `key` was already applied
+    pub async fn contains(&self, key: &EventKey) -> bool {
+        self.entries
+            .lock()
+            .await
+            .iter()
+            .any(|entry| &entry.key == key)
+    }
+
+    /// Marks the event identified by `key` as applied and persists it
+    pub async fn record(&self, key: EventKey, kind: JournalEventKind) -> Result<()> {
+        let entry = JournalEntry {
+            key,
+            kind,
+            applied_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        };
+        let mut line = serde_json::to_string(&entry)
+            .map_err(|e| GaiaError::Internal(format!("failed to encode journal entry: {}", e)))?;
+        line.push('\n');
+
+        // hold the lock while writing so lines from concurrent jobs don't interleave
+        let mut entries = self.entries.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", self.path.display(), e)))?;
+        file.write_all(line.as_bytes())
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", self.path.display(), e)))?;
+        file.sync_data()
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", self.path.display(), e)))?;
+
+        entries.push(entry);
+        Ok(())
+    }
+
+    /// All applied events in the order they were applied
+    pub async fn entries(&self) -> Vec<JournalEntry> {
+        self.entries.lock().await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn entries_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+
+        let key = EventKey {
+            block_hash: "0xabc".to_string(),
+            log_index: 3,
+            task_id: 7,
+        };
+
+        let journal = EventJournal::open(&path).await.unwrap();
+        assert!(!journal.contains(&key).await);
+        journal
+            .record(key.clone(), JournalEventKind::NodeStarted)
+            .await
+            .unwrap();
+
+        let reopened = EventJournal::open(&path).await.unwrap();
+        assert!(reopened.contains(&key).await);
+        assert!(
+            !reopened
+                .contains(&EventKey {
+                    log_index: 4,
+                    ..key.clone()
+                })
+                .await
+        );
+        assert_eq!(reopened.entries().await.len(), 1);
+    }
+}
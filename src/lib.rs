@@ -1,24 +1,35 @@
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use blueprint_sdk::alloy::primitives::{address, Address};
 use blueprint_sdk::alloy::rpc::types::Log;
 use blueprint_sdk::alloy::sol;
 use blueprint_sdk::config::GadgetConfiguration;
 use blueprint_sdk::event_listeners::evm::EvmContractEventListener;
 use blueprint_sdk::job;
-use blueprint_sdk::logging::{error, info, warn};
+use blueprint_sdk::logging::{debug, error, info, warn};
 use blueprint_sdk::macros::load_abi;
 use blueprint_sdk::std::sync::LazyLock;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::Mutex;
 
 pub mod actix_server;
+pub mod aggregator;
+pub mod attestation;
+pub mod challenger;
+pub mod dynamic_rag;
 pub mod gaia_manager;
+pub mod journal;
+pub mod metrics;
+pub mod node_logs;
+pub mod node_metrics;
+pub mod node_registry;
+pub mod node_state;
 pub mod qdrant;
+pub mod reconciler;
 pub mod runner;
 pub mod types;
 
-use gaia_manager::GaiaNodeManager;
+use journal::{EventJournal, EventKey, JournalEventKind};
+use metrics::METRICS;
+use node_registry::NodeRegistry;
 
 type ProcessorError =
     blueprint_sdk::event_listeners::core::Error<blueprint_sdk::event_listeners::evm::error::Error>;
@@ -45,7 +56,28 @@ pub static TASK_MANAGER_ADDRESS: LazyLock<Address> = LazyLock::new(|| {
 #[derive(Clone)]
 pub struct ExampleContext {
     pub config: GadgetConfiguration,
-    pub gaia_manager: Arc<Mutex<GaiaNodeManager>>,
+    pub nodes: NodeRegistry,
+    /// Events already applied by the jobs, consulted so redelivered events are skipped
+    pub journal: EventJournal,
+    /// Address of the operator key in our keystore, events for other operators are ignored
+    pub operator_address: Address,
+}
+
+impl ExampleContext {
+    /// Whether an event emitted for `operator` is addressed to us
+    pub fn is_our_operator(&self, operator: Address) -> bool {
+        operator == self.operator_address
+    }
+}
+
+/// Identifies the delivery of an event by the block and position of its log. Pending logs
+/// carry neither and can't be told apart, so they have no key.
+fn event_key(log: &Log, task_id: u32) -> Option<EventKey> {
+    Some(EventKey {
+        block_hash: log.block_hash?.to_string(),
+        log_index: log.log_index?,
+        task_id,
+    })
 }
 
 //// JOB DEFINITION TO HANDLE EVENTS
@@ -53,7 +85,7 @@ pub struct ExampleContext {
 // Add a start_gaia_node job that starts the Gaia node using our manager
 #[job(
     id = 1,
-    params(network, data_dir),
+    params(event_key, task_id, operator, timestamp, network, data_dir),
     event_listener(
         listener = EvmContractEventListener<ExampleContext, TangleTaskManager::GaiaNodeStarted>,
         instance = TangleTaskManager,
@@ -62,55 +94,113 @@ pub struct ExampleContext {
     ),
 )]
 pub async fn start_gaia_node(
-    _context: ExampleContext,
+    context: ExampleContext,
+    event_key: EventKey,
+    task_id: u32,
+    operator: Address,
+    timestamp: u64,
     network: Option<String>,
     data_dir: Option<String>,
 ) -> Result<(), Error> {
-    blueprint_sdk::logging::info!("Received request to start Gaia node");
+    let body = apply_start_gaia_node(
+        context, event_key, task_id, operator, timestamp, network, data_dir,
+    );
+    METRICS.job(1, "start_gaia_node", body).await
+}
+
+/// Starts the node requested by a `GaiaNodeStarted` event
+async fn apply_start_gaia_node(
+    context: ExampleContext,
+    event_key: EventKey,
+    task_id: u32,
+    operator: Address,
+    timestamp: u64,
+    network: Option<String>,
+    data_dir: Option<String>,
+) -> Result<(), Error> {
+    if !context.is_our_operator(operator) {
+        debug!(
+            "Ignoring GaiaNodeStarted for task {} of operator {}",
+            task_id, operator
+        );
+        return Ok(());
+    }
+
+    if context.journal.contains(&event_key).await {
+        info!(
+            "GaiaNodeStarted {} was already applied, skipping",
+            event_key
+        );
+        return Ok(());
+    }
 
+    blueprint_sdk::logging::info!(
+        "Received request to start Gaia node for task {} (requested at {})",
+        task_id,
+        timestamp
+    );
+
+    let (Some(network), Some(data_dir)) = (network, data_dir) else {
+        return Err(anyhow!(
+            "GaiaNodeStarted for task {} names no network or data dir",
+            task_id
+        ));
+    };
     let gaia_config = types::GaiaNodeConfig {
-        network: network.unwrap(),
-        data_dir: data_dir.unwrap(),
+        network,
+        data_dir,
         verbose: true,
+        port: None,
     };
 
-    let gaia_node_manager =
-        gaia_manager::GaiaNodeManager::new().expect("Failed to start Gaia Node Manager");
-    info!("Created a Gaia Node Manager");
-
-    // Store the manager in the context
-    {
-        let mut manager = _context.gaia_manager.lock().await;
-        *manager = Some(gaia_node_manager.clone()).unwrap();
+    // Every task gets its own supervised node, the registry hands out its port. The
+    // reconciler may have started it already if the event arrived late.
+    if context.nodes.is_active(task_id).await {
+        info!("Gaia node for task {} is already running", task_id);
+    } else if let Err(e) = context.nodes.start_node(task_id, gaia_config).await {
+        error!("Error starting Gaia node: {:?}", e);
+        return Err(e.into());
     }
 
-    // spawn a new thread to start gaia server
-    tokio::spawn(async move {
-        // this will stay running as long as this is running
-        if let Err(e) = gaia_node_manager.start(gaia_config).await {
-            error!("Error starting Gaia node: {:?}", e);
-        }
-    });
+    context
+        .journal
+        .record(event_key, JournalEventKind::NodeStarted)
+        .await?;
 
     Ok(())
 }
 
 /// Pre-processor for the start_gaia_node job
 async fn start_gaia_pre_processor(
-    (event, _log): (TangleTaskManager::GaiaNodeStarted, Log),
-) -> Result<Option<(Option<String>, Option<String>)>, ProcessorError> {
+    (event, log): (TangleTaskManager::GaiaNodeStarted, Log),
+) -> Result<Option<(EventKey, u32, Address, u64, Option<String>, Option<String>)>, ProcessorError> {
     match which::which("gaianet") {
         Ok(_) => {
             blueprint_sdk::logging::info!(
                 "Found gaianet installation, proceeding with node startup"
             );
 
+            let Some(key) = event_key(&log, event.taskId) else {
+                error!(
+                    "Rejecting GaiaNodeStarted for task {} without block hash or log index",
+                    event.taskId
+                );
+                return Ok(None);
+            };
+
             // Extract network and data_dir from the event
             let network = event.network.clone();
             let data_dir = event.dataDir.clone();
 
             // Return the extracted values
-            Ok(Some((Some(network), Some(data_dir))))
+            Ok(Some((
+                key,
+                event.taskId,
+                event.operator,
+                event.timestamp.saturating_to::<u64>(),
+                Some(network),
+                Some(data_dir),
+            )))
         }
         Err(_) => {
             blueprint_sdk::logging::error!(
@@ -124,7 +214,7 @@ async fn start_gaia_pre_processor(
 
 #[job(
     id = 2,
-    params(who),
+    params(event_key, task_id, operator, timestamp),
     event_listener(
         listener = EvmContractEventListener<ExampleContext, TangleTaskManager::GaiaNodeStopped>,
         instance = TangleTaskManager,
@@ -132,19 +222,81 @@ async fn start_gaia_pre_processor(
         pre_processor = stop_gaia_pre_processor,
     ),
 )]
-pub async fn stop_gaia_node(_context: ExampleContext, who: String) -> Result<String, Error> {
-    info!("Received request to stop Gaia node");
+pub async fn stop_gaia_node(
+    context: ExampleContext,
+    event_key: EventKey,
+    task_id: u32,
+    operator: Address,
+    timestamp: u64,
+) -> Result<String, Error> {
+    let body = apply_stop_gaia_node(context, event_key, task_id, operator, timestamp);
+    METRICS.job(2, "stop_gaia_node", body).await
+}
 
-    let gaia_node_manager = _context.gaia_manager.lock().await;
-    gaia_node_manager.stop().await?;
+/// Stops the node named by a `GaiaNodeStopped` event
+async fn apply_stop_gaia_node(
+    context: ExampleContext,
+    event_key: EventKey,
+    task_id: u32,
+    operator: Address,
+    timestamp: u64,
+) -> Result<String, Error> {
+    if !context.is_our_operator(operator) {
+        debug!(
+            "Ignoring GaiaNodeStopped for task {} of operator {}",
+            task_id, operator
+        );
+        return Ok(format!("Task {} belongs to another operator", task_id));
+    }
 
-    Ok("Successfully stopped Gaia node".to_string())
+    if context.journal.contains(&event_key).await {
+        info!(
+            "GaiaNodeStopped {} was already applied, skipping",
+            event_key
+        );
+        return Ok(format!("Task {} was already stopped", task_id));
+    }
+
+    info!(
+        "Received request to stop Gaia node for task {} (requested at {})",
+        task_id, timestamp
+    );
+
+    // a task without a node (e.g. stopped by the reconciler already) has nothing left to stop
+    if context.nodes.get(task_id).await.is_some() {
+        context.nodes.stop_node(task_id).await?;
+    } else {
+        info!(
+            "No Gaia node is registered for task {}, treating it as stopped",
+            task_id
+        );
+    }
+    context
+        .journal
+        .record(event_key, JournalEventKind::NodeStopped)
+        .await?;
+
+    Ok(format!(
+        "Successfully stopped Gaia node for task {}",
+        task_id
+    ))
 }
 
-/// Example pre-processor for handling inbound events
+/// Pre-processor for the stop_gaia_node job
 async fn stop_gaia_pre_processor(
-    (_event, log): (TangleTaskManager::GaiaNodeStopped, Log),
-) -> Result<Option<(String,)>, ProcessorError> {
-    let who = log.address();
-    Ok(Some((who.to_string(),)))
+    (event, log): (TangleTaskManager::GaiaNodeStopped, Log),
+) -> Result<Option<(EventKey, u32, Address, u64)>, ProcessorError> {
+    let Some(key) = event_key(&log, event.taskId) else {
+        error!(
+            "Rejecting GaiaNodeStopped for task {} without block hash or log index",
+            event.taskId
+        );
+        return Ok(None);
+    };
+    Ok(Some((
+        key,
+        event.taskId,
+        event.operator,
+        event.timestamp.saturating_to::<u64>(),
+    )))
 }
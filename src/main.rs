@@ -1,14 +1,21 @@
 use blueprint::{TangleTaskManager, TASK_MANAGER_ADDRESS};
 use blueprint_sdk::alloy::primitives::Address;
-use blueprint_sdk::logging::info;
+use blueprint_sdk::crypto::k256::K256Ecdsa;
+use blueprint_sdk::keystore::backends::Backend;
+use blueprint_sdk::logging::{error, info, warn};
 use blueprint_sdk::macros::main;
 use blueprint_sdk::runners::core::runner::BlueprintRunner;
 use blueprint_sdk::runners::eigenlayer::bls::EigenlayerBLSConfig;
 use blueprint_sdk::utils::evm::get_provider_http;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-
-use my_eigenlayer_avs_1::gaia_manager::GaiaNodeManager;
+use my_eigenlayer_avs_1::attestation::{self, DEFAULT_ATTESTATION_INTERVAL};
+use my_eigenlayer_avs_1::journal::EventJournal;
+use my_eigenlayer_avs_1::node_metrics::{self, DEFAULT_SAMPLE_INTERVAL};
+use my_eigenlayer_avs_1::node_registry::NodeRegistry;
+use my_eigenlayer_avs_1::node_state::NodeStateStore;
+use my_eigenlayer_avs_1::reconciler::{self, DEFAULT_RECONCILE_INTERVAL};
+use my_eigenlayer_avs_1::runner;
+use my_eigenlayer_avs_1::types::{state_dir, RestartPolicy, DEFAULT_NODE_PORT};
+use std::time::Duration;
 
 use my_eigenlayer_avs_1::{self as blueprint};
 
@@ -16,13 +23,83 @@ use my_eigenlayer_avs_1::{self as blueprint};
 async fn main() {
     // Create your service context
     // Here you can pass any configuration or context that your service needs.
-    let gaia_manager = Arc::new(Mutex::new(GaiaNodeManager::new().unwrap()));
+    // Each task's node gets its own port, handed out starting from GAIA_BASE_PORT
+    let base_port = std::env::var("GAIA_BASE_PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_NODE_PORT);
+    // Nodes that exit on their own are restarted with a growing delay, up to a retry limit
+    let defaults = RestartPolicy::default();
+    let secs = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+    };
+    let restart_policy = RestartPolicy {
+        max_retries: std::env::var("GAIA_RESTART_MAX_RETRIES")
+            .ok()
+            .and_then(|retries| retries.parse().ok())
+            .unwrap_or(defaults.max_retries),
+        initial_backoff: secs("GAIA_RESTART_INITIAL_BACKOFF_SECS")
+            .unwrap_or(defaults.initial_backoff),
+        max_backoff: secs("GAIA_RESTART_MAX_BACKOFF_SECS").unwrap_or(defaults.max_backoff),
+        reset_after: secs("GAIA_RESTART_RESET_AFTER_SECS").unwrap_or(defaults.reset_after),
+    };
+    // Nodes left running by a previous run of the service are picked up again
+    let nodes = NodeRegistry::new(base_port)
+        .with_restart_policy(restart_policy)
+        .with_state_store(NodeStateStore::new(state_dir().join("nodes")));
+    nodes.restore().await?;
+    let journal = EventJournal::open(state_dir().join("events.jsonl")).await?;
+
+    // The REST API shares the registry and journal with the jobs
+    {
+        let nodes = nodes.clone();
+        let journal = journal.clone();
+        tokio::spawn(async move {
+            if let Err(e) = runner::run(nodes, journal).await {
+                error!("Gaia node API server stopped: {}", e);
+            }
+        });
+    }
+
+    // Sample CPU, memory and disk usage of the nodes for /metrics/node
+    let sample_interval = std::env::var("GAIA_METRICS_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SAMPLE_INTERVAL);
+    tokio::spawn(node_metrics::run(nodes.clone(), sample_interval));
+
+    // Events are emitted for every operator of the AVS, we only act on our own
+    let operator = env.keystore().first_local::<K256Ecdsa>()?;
+    let operator_address = operator.alloy_address()?;
+    info!("Running as operator {}", operator_address);
 
     let context = blueprint::ExampleContext {
         config: env.clone(),
-        gaia_manager,
+        nodes,
+        journal,
+        operator_address,
     };
 
+    // Converge local nodes with the chain in case events were missed
+    let reconcile_interval = std::env::var("GAIA_RECONCILE_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RECONCILE_INTERVAL);
+    tokio::spawn(reconciler::run(context.clone(), reconcile_interval));
+
+    // Propose liveness attestations for our nodes and co-sign the ones of other operators
+    let attestation_interval = std::env::var("GAIA_ATTESTATION_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_ATTESTATION_INTERVAL);
+    tokio::spawn(attestation::run(context.clone(), attestation_interval));
+
     // Get the provider
     let rpc_endpoint = env.http_rpc_endpoint.clone();
     let provider = get_provider_http(&rpc_endpoint);
@@ -37,14 +114,40 @@ async fn main() {
         blueprint::StopGaiaNodeEventHandler::new(contract.clone(), context.clone());
 
     info!("Starting the event watcher ...");
-    let eigen_config = EigenlayerBLSConfig::new(Address::default(), Address::default());
-    BlueprintRunner::new(eigen_config, env)
+    // Rewards go to the operator, delegations need no approval unless an approver is set
+    let delegation_approver = std::env::var("DELEGATION_APPROVER_ADDRESS")
+        .map(|addr| addr.parse().expect("Invalid DELEGATION_APPROVER_ADDRESS"))
+        .unwrap_or(Address::ZERO);
+    let eigen_config = EigenlayerBLSConfig::new(operator_address, delegation_approver);
+    let runner = BlueprintRunner::new(eigen_config, env)
         .job(start_gaia_node)
         .job(stop_gaia_node)
-        .run()
-        .await?;
+        .run();
+
+    // Nodes are child processes of the blueprint, don't leave them running when it goes away
+    let result = tokio::select! {
+        result = runner => result,
+        signal = shutdown_signal() => {
+            info!("Received {}, shutting down", signal);
+            Ok(())
+        }
+    };
+    if let Err(e) = context.nodes.stop_all().await {
+        warn!("Not every Gaia node could be stopped: {}", e);
+    }
+    result?;
 
     info!("Exiting...");
 
     Ok(())
 }
+
+/// Resolves with the name of the first SIGINT or SIGTERM the process receives
+async fn shutdown_signal() -> &'static str {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("Failed to install the SIGTERM handler");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    }
+}
@@ -0,0 +1,275 @@
+use crate::types::GaiaNodeStatus;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::Registry;
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+/// Metrics of the whole service, served on `/metrics`
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Content type of `Metrics::encode` output
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// States reported by the `gaia_node_status` gauge
+const NODE_STATES: [&str; 5] = ["starting", "running", "syncing", "stopped", "error"];
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct NodeLabels {
+    task_id: u32,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct NodeStatusLabels {
+    task_id: u32,
+    status: &'static str,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct JobLabels {
+    job_id: u64,
+    job: &'static str,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RagLabels {
+    kind: &'static str,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RagOutcomeLabels {
+    kind: &'static str,
+    outcome: &'static str,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct HttpLabels {
+    method: String,
+    path: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct HttpStatusLabels {
+    method: String,
+    path: String,
+    status: u16,
+}
+
+type HistogramFamily<L> = Family<L, Histogram, fn() -> Histogram>;
+
+/// Buckets from 5ms to about 80s, covering API calls as well as model loading
+fn latency_histogram() -> Histogram {
+    Histogram::new(exponential_buckets(0.005, 2.0, 15))
+}
+
+/// Prometheus registry of the service together with handles to its metrics
+pub struct Metrics {
+    registry: Registry,
+    node_status: Family<NodeStatusLabels, Gauge>,
+    node_restarts: Family<NodeLabels, Counter>,
+    node_start_duration: Histogram,
+    node_stop_duration: Histogram,
+    job_executions: Family<JobLabels, Counter>,
+    job_failures: Family<JobLabels, Counter>,
+    rag_requests: Family<RagOutcomeLabels, Counter>,
+    rag_request_duration: HistogramFamily<RagLabels>,
+    http_requests: Family<HttpStatusLabels, Counter>,
+    http_request_duration: HistogramFamily<HttpLabels>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let mut metrics = Self {
+            registry: Registry::default(),
+            node_status: Family::default(),
+            node_restarts: Family::default(),
+            node_start_duration: latency_histogram(),
+            node_stop_duration: latency_histogram(),
+            job_executions: Family::default(),
+            job_failures: Family::default(),
+            rag_requests: Family::default(),
+            rag_request_duration: Family::new_with_constructor(latency_histogram),
+            http_requests: Family::default(),
+            http_request_duration: Family::new_with_constructor(latency_histogram),
+        };
+
+        let registry = &mut metrics.registry;
+        registry.register(
+            "gaia_node_status",
+            "1 for the state each node is in, 0 for the others",
+            metrics.node_status.clone(),
+        );
+        registry.register(
+            "gaia_node_restarts",
+            "Restarts of crashed nodes performed by their supervisor",
+            metrics.node_restarts.clone(),
+        );
+        registry.register(
+            "gaia_node_start_duration_seconds",
+            "Time from spawning a node until it answers its readiness probe",
+            metrics.node_start_duration.clone(),
+        );
+        registry.register(
+            "gaia_node_stop_duration_seconds",
+            "Time taken to stop a node until all its processes are gone",
+            metrics.node_stop_duration.clone(),
+        );
+        registry.register(
+            "gaia_job_executions",
+            "Executions of the blueprint jobs",
+            metrics.job_executions.clone(),
+        );
+        registry.register(
+            "gaia_job_failures",
+            "Executions of the blueprint jobs that returned an error",
+            metrics.job_failures.clone(),
+        );
+        registry.register(
+            "gaia_rag_requests",
+            "Embedding and chat requests sent by the RAG pipeline, retries included",
+            metrics.rag_requests.clone(),
+        );
+        registry.register(
+            "gaia_rag_request_duration_seconds",
+            "Duration of embedding and chat requests, retries included",
+            metrics.rag_request_duration.clone(),
+        );
+        registry.register(
+            "gaia_http_requests",
+            "Requests handled by the node API server",
+            metrics.http_requests.clone(),
+        );
+        registry.register(
+            "gaia_http_request_duration_seconds",
+            "Time the node API server took to respond",
+            metrics.http_request_duration.clone(),
+        );
+
+        metrics
+    }
+
+    /// Replaces the reported node states, nodes missing from `statuses` are dropped
+    pub fn set_node_statuses(&self, statuses: &[(u32, GaiaNodeStatus)]) {
+        self.node_status.clear();
+        for (task_id, status) in statuses {
+            let current = status_label(status);
+            for state in NODE_STATES {
+                self.node_status
+                    .get_or_create(&NodeStatusLabels {
+                        task_id: *task_id,
+                        status: state,
+                    })
+                    .set((state == current) as i64);
+            }
+        }
+    }
+
+    pub fn node_restarted(&self, task_id: u32) {
+        self.node_restarts
+            .get_or_create(&NodeLabels { task_id })
+            .inc();
+    }
+
+    pub fn node_started(&self, duration: Duration) {
+        self.node_start_duration.observe(duration.as_secs_f64());
+    }
+
+    pub fn node_stopped(&self, duration: Duration) {
+        self.node_stop_duration.observe(duration.as_secs_f64());
+    }
+
+    /// Runs the body of job `job_id` and counts the execution, and the failure if it errs
+    pub async fn job<T, E>(
+        &self,
+        job_id: u64,
+        job: &'static str,
+        body: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let result = body.await;
+        let labels = JobLabels { job_id, job };
+        self.job_executions.get_or_create(&labels).inc();
+        if result.is_err() {
+            self.job_failures.get_or_create(&labels).inc();
+        }
+        result
+    }
+
+    /// Runs a RAG request of `kind` (`embedding` or `chat`) and records its outcome and duration
+    pub async fn rag_request<T, E>(
+        &self,
+        kind: &'static str,
+        request: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let started = Instant::now();
+        let result = request.await;
+        self.rag_request_duration
+            .get_or_create(&RagLabels { kind })
+            .observe(started.elapsed().as_secs_f64());
+        let outcome = if result.is_ok() { "success" } else { "error" };
+        self.rag_requests
+            .get_or_create(&RagOutcomeLabels { kind, outcome })
+            .inc();
+        result
+    }
+
+    /// Records a request to the API server, `path` being the route pattern it matched
+    pub fn http_request(&self, method: &str, path: &str, status: u16, duration: Duration) {
+        self.http_requests
+            .get_or_create(&HttpStatusLabels {
+                method: method.to_string(),
+                path: path.to_string(),
+                status,
+            })
+            .inc();
+        self.http_request_duration
+            .get_or_create(&HttpLabels {
+                method: method.to_string(),
+                path: path.to_string(),
+            })
+            .observe(duration.as_secs_f64());
+    }
+
+    /// All metrics in the OpenMetrics text format
+    pub fn encode(&self) -> String {
+        let mut output = String::new();
+        // writing into a String can't fail
+        let _ = encode(&mut output, &self.registry);
+        output
+    }
+}
+
+fn status_label(status: &GaiaNodeStatus) -> &'static str {
+    match status {
+        GaiaNodeStatus::Starting => "starting",
+        GaiaNodeStatus::Running => "running",
+        GaiaNodeStatus::Syncing { .. } => "syncing",
+        GaiaNodeStatus::Stopped => "stopped",
+        GaiaNodeStatus::Error(_) => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_one_state_per_node() {
+        let metrics = Metrics::new();
+        metrics.set_node_statuses(&[(3, GaiaNodeStatus::Running)]);
+        metrics.set_node_statuses(&[(4, GaiaNodeStatus::Error("crashed".into()))]);
+        metrics.http_request("GET", "/status", 200, Duration::from_millis(3));
+
+        let output = metrics.encode();
+        assert!(output.contains(r#"gaia_node_status{task_id="4",status="error"} 1"#));
+        assert!(output.contains(r#"gaia_node_status{task_id="4",status="running"} 0"#));
+        assert!(!output.contains(r#"task_id="3""#));
+        assert!(output
+            .contains(r#"gaia_http_requests_total{method="GET",path="/status",status="200"} 1"#));
+        assert!(output.ends_with("# EOF\n"));
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// Lines kept per node when no capacity is configured
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A line written by a node process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLine {
+    /// Position of the line in the node's output, increasing across restarts
+    pub seq: u64,
+    /// Unix timestamp in seconds at which the line was read
+    pub timestamp: u64,
+    pub stream: LogStream,
+    pub line: String,
+}
+
+struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    next_seq: u64,
+}
+
+/// Ring buffer holding the last lines written by a node, with a live feed of new lines
+#[derive(Clone)]
+pub struct NodeLogs {
+    buffer: Arc<Mutex<LogBuffer>>,
+    capacity: usize,
+    sender: broadcast::Sender<LogLine>,
+}
+
+impl Default for NodeLogs {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_CAPACITY)
+    }
+}
+
+impl NodeLogs {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            buffer: Arc::new(Mutex::new(LogBuffer {
+                lines: VecDeque::with_capacity(capacity),
+                next_seq: 0,
+            })),
+            capacity,
+            sender,
+        }
+    }
+
+    /// Appends a line, dropping the oldest one once the buffer is full
+    pub fn push(&self, stream: LogStream, line: String) -> LogLine {
+        let mut buffer = self.buffer.lock().unwrap();
+        let entry = LogLine {
+            seq: buffer.next_seq,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            stream,
+            line,
+        };
+        buffer.next_seq += 1;
+        if buffer.lines.len() == self.capacity {
+            buffer.lines.pop_front();
+        }
+        buffer.lines.push_back(entry.clone());
+
+        // Sent while holding the lock so subscribers see lines in order; no subscriber is fine
+        let _ = self.sender.send(entry.clone());
+        entry
+    }
+
+    /// Buffered lines read at or after `since` (unix seconds), at most the last `tail` of them
+    pub fn lines(&self, tail: Option<usize>, since: Option<u64>) -> Vec<LogLine> {
+        let buffer = self.buffer.lock().unwrap();
+        let lines: Vec<LogLine> = buffer
+            .lines
+            .iter()
+            .filter(|line| since.map_or(true, |since| line.timestamp >= since))
+            .cloned()
+            .collect();
+
+        match tail {
+            Some(tail) if tail < lines.len() => lines[lines.len() - tail..].to_vec(),
+            _ => lines,
+        }
+    }
+
+    /// Receives every line pushed from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<LogLine> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_last_lines() {
+        let logs = NodeLogs::new(3);
+        for i in 0..5 {
+            logs.push(LogStream::Stdout, format!("line {}", i));
+        }
+
+        let lines = logs.lines(None, None);
+        assert_eq!(
+            lines.iter().map(|line| line.seq).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert_eq!(logs.lines(Some(1), None)[0].line, "line 4");
+        assert!(logs.lines(None, Some(u64::MAX)).is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+use crate::node_registry::NodeRegistry;
+use crate::types::ResourceUsage;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Default delay between two samples of a node's resource usage
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Samples kept per node when no history size is configured, an hour at the default interval
+pub const DEFAULT_METRICS_HISTORY: usize = 360;
+
+/// Ring buffer holding the last resource usage samples taken for a node
+#[derive(Clone)]
+pub struct NodeMetrics {
+    history: Arc<Mutex<VecDeque<ResourceUsage>>>,
+    capacity: usize,
+}
+
+impl Default for NodeMetrics {
+    fn default() -> Self {
+        Self::new(DEFAULT_METRICS_HISTORY)
+    }
+}
+
+impl NodeMetrics {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            history: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Appends a sample, dropping the oldest one once the buffer is full
+    pub fn record(&self, usage: ResourceUsage) {
+        let mut history = self.history.lock().unwrap();
+        if history.len() == self.capacity {
+            history.pop_front();
+        }
+        history.push_back(usage);
+    }
+
+    /// Most recent sample
+    pub fn latest(&self) -> Option<ResourceUsage> {
+        self.history.lock().unwrap().back().cloned()
+    }
+
+    /// Buffered samples, oldest first
+    pub fn history(&self) -> Vec<ResourceUsage> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Periodically samples the resource usage of every registered node. One process table is
+/// kept across samples, CPU usage is measured between two consecutive refreshes.
+pub async fn run(nodes: NodeRegistry, interval: Duration) {
+    let mut system = System::new();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+
+        for task_id in nodes.task_ids().await {
+            if let Some(node) = nodes.get(task_id).await {
+                node.sample_resources(&system).await;
+            }
+        }
+    }
+}
+
+/// CPU, memory and file descriptors used by `roots` and all their descendants
+pub(crate) struct ProcessTreeUsage {
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub open_files: u64,
+    pub processes: usize,
+}
+
+pub(crate) fn process_tree_usage(system: &System, roots: &[Pid]) -> ProcessTreeUsage {
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, process) in system.processes() {
+        // threads are listed next to their process, which already accounts for them
+        if process.thread_kind().is_some() {
+            continue;
+        }
+        if let Some(parent) = process.parent() {
+            children.entry(parent).or_default().push(*pid);
+        }
+    }
+
+    let mut usage = ProcessTreeUsage {
+        cpu_percent: 0.0,
+        memory_bytes: 0,
+        open_files: 0,
+        processes: 0,
+    };
+    let mut seen = Vec::new();
+    let mut pending = roots.to_vec();
+    while let Some(pid) = pending.pop() {
+        if seen.contains(&pid) {
+            continue;
+        }
+        seen.push(pid);
+        let Some(process) = system.process(pid) else {
+            continue;
+        };
+
+        usage.cpu_percent += process.cpu_usage();
+        usage.memory_bytes += process.memory();
+        usage.open_files += open_files(pid);
+        usage.processes += 1;
+        if let Some(descendants) = children.get(&pid) {
+            pending.extend(descendants);
+        }
+    }
+    usage
+}
+
+/// File descriptors held by `pid`, only known on Linux
+fn open_files(pid: Pid) -> u64 {
+    std::fs::read_dir(format!("/proc/{}/fd", pid))
+        .map(|entries| entries.count() as u64)
+        .unwrap_or(0)
+}
+
+/// Bytes taken by the files below `path`, symlinks are not followed
+pub(crate) fn disk_usage(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => disk_usage(&entry.path()),
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_tree_includes_children() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+
+        let mut system = System::new();
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+        let own = process_tree_usage(&system, &[Pid::from_u32(child.id())]);
+        let tree = process_tree_usage(&system, &[Pid::from_u32(std::process::id())]);
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(own.processes, 1);
+        assert!(tree.processes >= 2);
+        assert!(tree.memory_bytes > own.memory_bytes);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::gaia_manager::GaiaNodeManager;
+use crate::node_state::NodeStateStore;
+use crate::types::{
+    GaiaError, GaiaNodeConfig, GaiaNodeStatus, RestartPolicy, Result, DEFAULT_NODE_PORT,
+};
+use blueprint_sdk::logging::{info, warn};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Task id used for nodes started manually through the REST API; on-chain task ids start at 1
+pub const LOCAL_TASK_ID: u32 = 0;
+
+/// Keeps one `GaiaNodeManager` per on-chain task so an operator can host several nodes
+#[derive(Clone)]
+pub struct NodeRegistry {
+    nodes: Arc<Mutex<BTreeMap<u32, GaiaNodeManager>>>,
+    // first port handed out to nodes that don't configure one
+    base_port: u16,
+    // where managers record their node processes, `None` keeps nothing across restarts
+    state: Option<NodeStateStore>,
+    // how every manager restarts a node that exited on its own
+    restart_policy: RestartPolicy,
+}
+
+impl Default for NodeRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_NODE_PORT)
+    }
+}
+
+impl NodeRegistry {
+    pub fn new(base_port: u16) -> Self {
+        Self {
+            nodes: Arc::new(Mutex::new(BTreeMap::new())),
+            base_port,
+            state: None,
+            restart_policy: RestartPolicy::default(),
+        }
+    }
+
+    /// Records the nodes in `state` so they can be restored after the service restarts
+    pub fn with_state_store(mut self, state: NodeStateStore) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Restarts the nodes that exit on their own according to `restart_policy`
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = restart_policy;
+        self
+    }
+
+    fn new_manager(&self, task_id: u32) -> Result<GaiaNodeManager> {
+        let manager = GaiaNodeManager::new()?
+            .with_task_id(task_id)
+            .with_restart_policy(self.restart_policy.clone());
+        Ok(match &self.state {
+            Some(state) => manager.with_state_store(state.clone()),
+            None => manager,
+        })
+    }
+
+    /// Registers the nodes recorded by a previous run of the service. Nodes that are still
+    /// alive are re-attached to, the others are started again.
+    pub async fn restore(&self) -> Result<()> {
+        let Some(state) = &self.state else {
+            return Ok(());
+        };
+
+        for record in state.load_all().await? {
+            let task_id = record.task_id;
+            if self.get(task_id).await.is_some() {
+                continue;
+            }
+
+            let manager = self.new_manager(task_id)?;
+            manager.adopt(record).await;
+            info!(
+                "Restored Gaia node for task {}: {}",
+                task_id,
+                manager.get_status().await
+            );
+            self.nodes.lock().await.insert(task_id, manager);
+        }
+
+        Ok(())
+    }
+
+    /// Starts a node for `task_id`, giving it its own port unless the config sets one.
+    /// Fails if the task already has an active node or another registered node uses the same
+    /// data dir or port.
+    pub async fn start_node(&self, task_id: u32, mut config: GaiaNodeConfig) -> Result<()> {
+        let (manager, previous) = {
+            let mut nodes = self.nodes.lock().await;
+
+            let mut used_ports = Vec::new();
+            for (id, node) in nodes.iter() {
+                if *id == task_id {
+                    if is_active(&node.get_status().await) {
+                        return Err(GaiaError::InvalidState(format!(
+                            "task {} already has a running node",
+                            task_id
+                        )));
+                    }
+                    continue;
+                }
+
+                if let Some(other) = node.get_config().await {
+                    if other.data_path() == config.data_path() {
+                        return Err(GaiaError::InvalidState(format!(
+                            "data dir {} is already used by task {}",
+                            config.data_dir, id
+                        )));
+                    }
+                    used_ports.push(port_of(&other));
+                }
+            }
+
+            let port = match config.port {
+                Some(port) if used_ports.contains(&port) => {
+                    return Err(GaiaError::InvalidState(format!(
+                        "port {} is already used by another node",
+                        port
+                    )));
+                }
+                Some(port) => port,
+                None => (self.base_port..=u16::MAX)
+                    .find(|port| !used_ports.contains(port))
+                    .ok_or_else(|| GaiaError::Internal("no free port left".into()))?,
+            };
+            config.port = Some(port);
+
+            let manager = self.new_manager(task_id)?;
+            let previous = nodes.insert(task_id, manager.clone());
+            (manager, previous)
+        };
+
+        // a failed node may still be inside its restart backoff, make sure it is gone
+        if let Some(previous) = previous {
+            if let Err(e) = previous.stop().await {
+                warn!("Failed to stop previous node of task {}: {}", task_id, e);
+            }
+        }
+
+        info!(
+            "Starting Gaia node for task {} on port {}",
+            task_id,
+            port_of(&config)
+        );
+        // the node stays registered on failure so its Error status can be inspected
+        manager.start(config).await
+    }
+
+    /// Stops the node running for `task_id` and forgets about it
+    pub async fn stop_node(&self, task_id: u32) -> Result<()> {
+        let manager = self.get(task_id).await.ok_or_else(|| {
+            GaiaError::InvalidState(format!("no node is registered for task {}", task_id))
+        })?;
+
+        info!("Stopping Gaia node for task {}", task_id);
+        manager.stop().await?;
+        self.nodes.lock().await.remove(&task_id);
+
+        Ok(())
+    }
+
+    /// Stops every registered node, used when the service shuts down. Nodes that fail to stop
+    /// stay registered and the last error is returned.
+    pub async fn stop_all(&self) -> Result<()> {
+        let mut result = Ok(());
+        for task_id in self.task_ids().await {
+            if let Err(e) = self.stop_node(task_id).await {
+                warn!("Failed to stop Gaia node of task {}: {}", task_id, e);
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Manager of the node running for `task_id`
+    pub async fn get(&self, task_id: u32) -> Option<GaiaNodeManager> {
+        self.nodes.lock().await.get(&task_id).cloned()
+    }
+
+    /// Whether `task_id` has a node that is starting, running or syncing
+    pub async fn is_active(&self, task_id: u32) -> bool {
+        match self.get(task_id).await {
+            Some(node) => is_active(&node.get_status().await),
+            None => false,
+        }
+    }
+
+    /// Ids of all tasks that have a registered node
+    pub async fn task_ids(&self) -> Vec<u32> {
+        self.nodes.lock().await.keys().copied().collect()
+    }
+
+    /// Status of every registered node, ordered by task id
+    pub async fn statuses(&self) -> Vec<(u32, GaiaNodeStatus)> {
+        let nodes: Vec<_> = self
+            .nodes
+            .lock()
+            .await
+            .iter()
+            .map(|(id, node)| (*id, node.clone()))
+            .collect();
+
+        let mut statuses = Vec::with_capacity(nodes.len());
+        for (id, node) in nodes {
+            statuses.push((id, node.get_status().await));
+        }
+        statuses
+    }
+}
+
+fn port_of(config: &GaiaNodeConfig) -> u16 {
+    config.port.unwrap_or(DEFAULT_NODE_PORT)
+}
+
+fn is_active(status: &GaiaNodeStatus) -> bool {
+    !matches!(status, GaiaNodeStatus::Stopped | GaiaNodeStatus::Error(_))
+}
@@ -0,0 +1,139 @@
+use crate::types::{GaiaError, GaiaNodeConfig, Result};
+use blueprint_sdk::logging::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What the service remembers about a node it spawned, so the node can be found again after
+/// the service restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeRecord {
+    pub task_id: u32,
+    /// Pid of the `gaianet start` process, also the id of the node's process group
+    pub pid: u32,
+    pub config: GaiaNodeConfig,
+    /// Unix time (seconds) the node process was spawned at
+    pub started_at: u64,
+}
+
+/// Keeps one `NodeRecord` per task as `<dir>/<task_id>.json`
+#[derive(Debug, Clone)]
+pub struct NodeStateStore {
+    dir: PathBuf,
+}
+
+impl NodeStateStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn record_path(&self, task_id: u32) -> PathBuf {
+        self.dir.join(format!("{}.json", task_id))
+    }
+
+    /// Persists `record`, replacing the previous record of its task
+    pub async fn save(&self, record: &NodeRecord) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", self.dir.display(), e)))?;
+
+        let raw = serde_json::to_vec_pretty(record)
+            .map_err(|e| GaiaError::Internal(format!("failed to encode node record: {}", e)))?;
+        // written next to the record and renamed over it so a crash never leaves half a file
+        let path = self.record_path(record.task_id);
+        let staging = path.with_extension("json.tmp");
+        tokio::fs::write(&staging, raw)
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", staging.display(), e)))?;
+        tokio::fs::rename(&staging, &path)
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Forgets the node of `task_id`, doing nothing if no record exists
+    pub async fn remove(&self, task_id: u32) -> Result<()> {
+        let path = self.record_path(task_id);
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(GaiaError::Io(format!("{}: {}", path.display(), e))),
+        }
+    }
+
+    /// Every persisted record, ordered by task id. Unreadable records are skipped.
+    pub async fn load_all(&self) -> Result<Vec<NodeRecord>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(GaiaError::Io(format!("{}: {}", self.dir.display(), e))),
+        };
+
+        let mut records = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| GaiaError::Io(format!("{}: {}", self.dir.display(), e)))?
+        {
+            let path = entry.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "json")
+            {
+                continue;
+            }
+
+            let record = tokio::fs::read(&path)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|raw| {
+                    serde_json::from_slice::<NodeRecord>(&raw).map_err(|e| e.to_string())
+                });
+            match record {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("Skipping unreadable node record {}: {}", path.display(), e),
+            }
+        }
+
+        records.sort_by_key(|record| record.task_id);
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn records_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = NodeStateStore::new(dir.path());
+        assert!(store.load_all().await.unwrap().is_empty());
+        for task_id in [4, 2] {
+            store
+                .save(&NodeRecord {
+                    task_id,
+                    pid: 100 + task_id,
+                    config: GaiaNodeConfig::default(),
+                    started_at: 1_700_000_000,
+                })
+                .await
+                .unwrap();
+        }
+        std::fs::write(dir.path().join("7.json"), "{").unwrap();
+
+        let records = NodeStateStore::new(dir.path()).load_all().await.unwrap();
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record.task_id, record.pid))
+                .collect::<Vec<_>>(),
+            vec![(2, 102), (4, 104)]
+        );
+
+        store.remove(4).await.unwrap();
+        store.remove(4).await.unwrap();
+        assert_eq!(store.load_all().await.unwrap().len(), 1);
+    }
+}
@@ -1,2 +1 @@
 pub mod qdrant_client;
-pub mod utils;
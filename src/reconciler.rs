@@ -0,0 +1,149 @@
+use crate::node_registry::LOCAL_TASK_ID;
+use crate::types::{GaiaNodeConfig, GaiaNodeStatus};
+use crate::{ExampleContext, TangleTaskManager, TASK_MANAGER_ADDRESS};
+use anyhow::Result;
+use blueprint_sdk::logging::{error, info, warn};
+use blueprint_sdk::utils::evm::get_provider_http;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Default delay between two reconciliation passes
+pub const DEFAULT_RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What a single reconciliation pass changed locally
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub started: Vec<u32>,
+    pub stopped: Vec<u32>,
+}
+
+/// Periodically converges the local nodes with the tasks the chain assigns to our operator.
+/// Covers events that were missed while the service was down and nodes that were started or
+/// stopped by hand.
+pub async fn run(context: ExampleContext, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        match reconcile(&context).await {
+            Ok(report) if report.started.is_empty() && report.stopped.is_empty() => {}
+            Ok(report) => info!(
+                "Reconciled Gaia nodes with the chain: started {:?}, stopped {:?}",
+                report.started, report.stopped
+            ),
+            Err(e) => error!("Failed to reconcile Gaia nodes with the chain: {}", e),
+        }
+    }
+}
+
+/// Runs one reconciliation pass
+pub async fn reconcile(context: &ExampleContext) -> Result<ReconcileReport> {
+    let provider = get_provider_http(&context.config.http_rpc_endpoint);
+    let contract = TangleTaskManager::new(*TASK_MANAGER_ADDRESS, provider);
+
+    // stopped tasks are dropped from the list, their nodes are caught by the sweep below
+    let task_ids = contract
+        .getOperatorTasks(context.operator_address)
+        .call()
+        .await?
+        ._0;
+
+    let mut report = ReconcileReport::default();
+    let mut running_on_chain = BTreeSet::new();
+
+    for task_id in task_ids {
+        let on_chain = contract.getGaiaNodeStatus(task_id).call().await?._0;
+        let locally_active = context.nodes.is_active(task_id).await;
+
+        if on_chain.isRunning {
+            running_on_chain.insert(task_id);
+        }
+
+        match (on_chain.isRunning, locally_active) {
+            (true, false) => {
+                match failed_node(context, task_id).await {
+                    Some((reason, true)) => {
+                        // restarting here would bypass the supervisor's restart policy
+                        warn!(
+                            "Drift on task {}: running on chain but the local node failed, \
+                             its supervisor is still retrying: {}",
+                            task_id, reason
+                        );
+                        continue;
+                    }
+                    Some((reason, false)) => warn!(
+                        "Drift on task {}: running on chain but the local node failed for good, \
+                         restarting it: {}",
+                        task_id, reason
+                    ),
+                    None => warn!(
+                        "Drift on task {}: running on chain but not locally, starting it",
+                        task_id
+                    ),
+                }
+                let chain_config = contract.getGaiaNodeConfig(task_id).call().await?._0;
+                let config = GaiaNodeConfig {
+                    network: chain_config.network,
+                    data_dir: chain_config.dataDir,
+                    verbose: true,
+                    port: None,
+                };
+                match context.nodes.start_node(task_id, config).await {
+                    Ok(()) => report.started.push(task_id),
+                    Err(e) => error!("Failed to start Gaia node for task {}: {}", task_id, e),
+                }
+            }
+            (false, true) => {
+                warn!(
+                    "Drift on task {}: stopped on chain but running locally, stopping it",
+                    task_id
+                );
+                match context.nodes.stop_node(task_id).await {
+                    Ok(()) => report.stopped.push(task_id),
+                    Err(e) => error!("Failed to stop Gaia node for task {}: {}", task_id, e),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // nodes for tasks the chain doesn't assign to us anymore
+    for task_id in context.nodes.task_ids().await {
+        if task_id == LOCAL_TASK_ID || running_on_chain.contains(&task_id) {
+            continue;
+        }
+        if report.stopped.contains(&task_id) {
+            continue;
+        }
+
+        if context.nodes.is_active(task_id).await {
+            warn!(
+                "Drift on task {}: node runs locally without a running task on chain, stopping it",
+                task_id
+            );
+        } else if let Some((reason, false)) = failed_node(context, task_id).await {
+            warn!(
+                "Drift on task {}: node failed for good without a running task on chain, \
+                 removing it: {}",
+                task_id, reason
+            );
+        } else {
+            continue;
+        }
+        match context.nodes.stop_node(task_id).await {
+            Ok(()) => report.stopped.push(task_id),
+            Err(e) => error!("Failed to stop Gaia node for task {}: {}", task_id, e),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Why the local node of `task_id` failed and whether its supervisor is still retrying,
+/// `None` unless the node is in the `Error` state
+async fn failed_node(context: &ExampleContext, task_id: u32) -> Option<(String, bool)> {
+    let node = context.nodes.get(task_id).await?;
+    match node.get_status().await {
+        GaiaNodeStatus::Error(reason) => Some((reason, node.is_supervised().await)),
+        _ => None,
+    }
+}
@@ -2,6 +2,7 @@ use dirs;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::PathBuf;
+use std::time::Duration;
 
 pub type Result<T> = std::result::Result<T, GaiaError>;
 
@@ -63,11 +64,101 @@ impl fmt::Display for GaiaNodeStatus {
     }
 }
 
+/// Directory the service keeps its own state in (event journal, node records), taken from
+/// `GAIA_STATE_DIR` and defaulting to `~/.gaia-avs`
+pub fn state_dir() -> PathBuf {
+    std::env::var("GAIA_STATE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("/tmp"))
+                .join(".gaia-avs")
+        })
+}
+
+/// Public URL of a node's OpenAI-compatible API, reachable through the Gaia domain tunnel
+/// under its node id
+pub fn public_node_url(node_id: &str) -> String {
+    format!("https://{}.gaia.domains/v1", node_id)
+}
+
+/// Port gaianet serves its OpenAI-compatible API on when none is configured
+pub const DEFAULT_NODE_PORT: u16 = 8080;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GaiaNodeConfig {
     pub data_dir: String,
     pub network: String,
     pub verbose: bool,
+    /// Port of the node's API, `None` keeps whatever the data dir is configured with
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+/// `path` with a leading `~` expanded to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~") {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+impl GaiaNodeConfig {
+    /// Data directory with a leading `~` expanded to the home directory
+    pub fn data_path(&self) -> PathBuf {
+        expand_home(&self.data_dir)
+    }
+
+    /// Base URL of the node's OpenAI-compatible API
+    pub fn api_url(&self) -> String {
+        format!(
+            "http://localhost:{}/v1",
+            self.port.unwrap_or(DEFAULT_NODE_PORT)
+        )
+    }
+
+    /// Named gaianet config preset (or URL) to initialize the data dir with, `None` for the
+    /// default preset used by mainnet
+    pub fn network_preset(&self) -> Option<&str> {
+        match self.network.as_str() {
+            "mainnet" => None,
+            network => Some(network),
+        }
+    }
+
+    /// Checks that the config can be turned into a gaianet invocation
+    pub fn validate(&self) -> Result<()> {
+        if self.network.trim().is_empty() {
+            return Err(GaiaError::InvalidState("network cannot be empty".into()));
+        }
+        if self.network.chars().any(char::is_whitespace) {
+            return Err(GaiaError::InvalidState(format!(
+                "invalid network name: {:?}",
+                self.network
+            )));
+        }
+        if self.data_dir.trim().is_empty() {
+            return Err(GaiaError::InvalidState("data_dir cannot be empty".into()));
+        }
+
+        let data_path = self.data_path();
+        if !data_path.is_absolute() {
+            return Err(GaiaError::InvalidState(format!(
+                "data_dir must be an absolute path: {}",
+                self.data_dir
+            )));
+        }
+        if data_path.exists() && !data_path.is_dir() {
+            return Err(GaiaError::InvalidState(format!(
+                "data_dir is not a directory: {}",
+                data_path.display()
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for GaiaNodeConfig {
@@ -78,6 +169,78 @@ impl Default for GaiaNodeConfig {
             data_dir: home_dir.join(".gaianet").to_string_lossy().to_string(),
             network: "mainnet".to_string(),
             verbose: false,
+            port: None,
+        }
+    }
+}
+
+/// Controls how the manager restarts a node that exited without being asked to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartPolicy {
+    /// Consecutive restarts attempted before the node is left in the `Error` state
+    pub max_retries: u32,
+    /// Delay before the first restart, doubled on every following attempt
+    pub initial_backoff: Duration,
+    /// Upper bound for the restart delay
+    pub max_backoff: Duration,
+    /// A node that stays up this long is considered healthy again and the retry count resets
+    pub reset_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            reset_after: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Delay to wait before restart number `attempt` (starting at 0)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Controls how the manager shuts a node down. `gaianet stop` is tried first, processes
+/// still alive afterwards get SIGTERM and finally SIGKILL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownPolicy {
+    /// How long `gaianet stop` may run before it is abandoned
+    pub stop_timeout: Duration,
+    /// How long the node processes get to exit after each step before escalating
+    pub grace_period: Duration,
+}
+
+impl Default for ShutdownPolicy {
+    fn default() -> Self {
+        Self {
+            stop_timeout: Duration::from_secs(30),
+            grace_period: Duration::from_secs(10),
+        }
+    }
+}
+
+/// HTTP readiness check run against the node's OpenAI-compatible API after it is spawned.
+/// The node is ready once `GET {api_url}/models` answers with a success status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessProbe {
+    /// Delay between two probes, also used as the per-request timeout
+    pub interval: Duration,
+    /// How long the node may take to become ready before it is considered failed
+    pub startup_timeout: Duration,
+}
+
+impl Default for ReadinessProbe {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(2),
+            startup_timeout: Duration::from_secs(300),
         }
     }
 }
@@ -87,8 +250,16 @@ pub struct NodeInfo {
     pub version: String,
     pub network: String,
     pub node_id: String,
-    pub peers: u32,
+    /// Number of connected peers. gaianet doesn't expose one, nodes are reached through the
+    /// Gaia domain tunnel instead of a peer set, so this is always `None` for now.
+    #[serde(default)]
+    pub peers: Option<u32>,
+    /// Sync progress of the node. gaianet doesn't report one, so this is always `None` for now.
+    #[serde(default)]
     pub sync_status: Option<SyncStatus>,
+    /// Latest resource usage sample of the node, `None` until it was first sampled
+    #[serde(default)]
+    pub resources: Option<ResourceUsage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,3 +268,19 @@ pub struct SyncStatus {
     pub target_height: u64,
     pub progress: f64,
 }
+
+/// Resources used by a node's process tree and data dir at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Unix timestamp in seconds at which the sample was taken
+    pub timestamp: u64,
+    /// Summed over the node's processes, 100 is one fully used core
+    pub cpu_percent: f32,
+    /// Resident memory of the node's processes
+    pub memory_bytes: u64,
+    /// File descriptors held by the node's processes, always 0 outside Linux
+    pub open_files: u64,
+    /// Size of the files in the node's data dir
+    pub disk_bytes: u64,
+    pub processes: usize,
+}